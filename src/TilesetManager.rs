@@ -1,4 +1,4 @@
-use std::fs;
+use std::{collections::HashMap, fs};
 
 use bespoke_engine::{binding::UniformBinding, texture::Texture};
 use cgmath::Vector2;
@@ -6,7 +6,7 @@ use image::{GenericImageView, ImageBuffer, Pixel, Rgb, Rgba};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::physics::boxCollider::BoxCollider;
+use crate::{save::Ability, physics::{boxCollider::{BoxCollider, ColliderId, ColliderKind}, broadphase::SpatialGrid, query::{sweep_colliders, RayHit}, colliderGen::{SolidMap, TileRect}, terrain::Terrain, tileGrid::TileGridTerrain, tileShape::TileShape}, tiles::Tiles};
 
 #[derive(Serialize, Deserialize)]
 struct Tilemap {
@@ -14,6 +14,42 @@ struct Tilemap {
     mapWidth: u32,
     tileSize: u32,
     layers: Vec<Layer>,
+    //not written by spritefusion, added by hand to give tile ids collision other than plain solid
+    #[serde(default)]
+    tileCollision: HashMap<String, TileCollision>,
+    //also added by hand, tiles the player respawns at after touching them
+    #[serde(default)]
+    checkpoints: Vec<Checkpoint>,
+    //also added by hand, tiles that unlock an ability when touched
+    #[serde(default)]
+    pickups: Vec<Pickup>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    x: u32,
+    y: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Pickup {
+    x: u32,
+    y: u32,
+    ability: Ability,
+}
+
+//how a tile collides, tiles without an entry in tileCollision are Solid
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum TileCollision {
+    Solid,
+    None,
+    OneWay,
+    Hazard,
+    //solid rectangles inside the tile, each is [x, y, width, height] as fractions of the tile with y going down
+    Custom { rects: Vec<[f32; 4]> },
+    //one of the preset partial or sloped shapes
+    Shape { shape: TileShape },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -32,24 +68,122 @@ struct Tile {
 
 
 pub struct TilesetManager {
+    //everything that isn't a plain solid tile, like one way platforms, hazards and slopes
     pub colliders: Vec<BoxCollider>,
+    pub grid: SpatialGrid,
+    //plain solid tiles packed the same way as Tiles::solid, x * map_height + y
+    //they are collided with cell by cell, so set_solid changes collision straight away
+    pub solid: Vec<u32>,
+    pub map_width: u32,
+    pub map_height: u32,
+    //size of one tile in world units
+    pub tile_size: f32,
+    //world space areas of the checkpoint tiles
+    pub checkpoints: Vec<BoxCollider>,
+    //world space areas of the ability pickups and what they unlock
+    pub pickups: Vec<(BoxCollider, Ability)>,
 }
 
 impl TilesetManager {
+    //loads the collision of a map, this doesn't touch any images so it works without a window
     pub fn new(json_path: &str, map_width: u32) -> Self {
-        //read the json of the tilemap
-        //using https://www.spritefusion.com/editor as tilemap editor
-        let json_contents = fs::read_to_string(json_path).expect("Couldn't read JSON");
-        let data : Tilemap = serde_json::from_str(&json_contents).unwrap();
+        let data = read_map(json_path);
+
+        let mut colliders = Vec::new();
+        let scale_factor = map_width as f32 / (data.mapWidth * data.tileSize) as f32;
+        let mut grid = SpatialGrid::new(data.tileSize as f32 * scale_factor);
+        let mut solid = vec![0; ((data.mapWidth * data.mapHeight) as f32 / 32.0).ceil() as usize];
+        let mut one_way_map = SolidMap::new(data.mapWidth, data.mapHeight);
+        let mut hazard_map = SolidMap::new(data.mapWidth, data.mapHeight);
+        let mut custom_rects = Vec::new();
+        let mut slopes = Vec::new();
+
+        //decoration layers never collide
+        for layer in data.layers.into_iter().rev().filter(|layer| layer.collider) {
+            for tile in layer.tiles {
+                let xPos = tile.x;
+                let yPos = tile.y;
+                //outside the map a tile would land in the next column's cell or past the end of the bitset
+                if xPos >= data.mapWidth || yPos >= data.mapHeight {
+                    println!("skipping tile {} at ({xPos}, {yPos}), outside the {}x{} map", tile.id, data.mapWidth, data.mapHeight);
+                    continue;
+                }
+                match data.tileCollision.get(&tile.id).unwrap_or(&TileCollision::Solid) {
+                    TileCollision::Solid => Tiles::set_solid_static(&mut solid, (xPos * data.mapHeight + yPos) as usize, true),
+                    TileCollision::None => {},
+                    TileCollision::OneWay => one_way_map.set(xPos, yPos, true),
+                    TileCollision::Hazard => hazard_map.set(xPos, yPos, true),
+                    TileCollision::Custom { rects } => {
+                        for rect in rects {
+                            custom_rects.push([xPos as f32 + rect[0], yPos as f32 + rect[1], rect[2], rect[3]]);
+                        }
+                    },
+                    TileCollision::Shape { shape } => {
+                        if *shape == TileShape::Full {
+                            Tiles::set_solid_static(&mut solid, (xPos * data.mapHeight + yPos) as usize, true);
+                        } else if let Some(rect) = shape.rect() {
+                            custom_rects.push([xPos as f32 + rect[0], yPos as f32 + rect[1], rect[2], rect[3]]);
+                        } else if let Some([left, right]) = shape.slope() {
+                            //tile x is flipped in world space, so the right edge of the tile ends up at the collider's min x
+                            slopes.push(([xPos as f32, yPos as f32, 1.0, 1.0], [right, left]));
+                        }
+                    },
+                }
+            }
+        }
+
+        //one collider per merged rectangle instead of one per tile
+        //ids carry on after the solid cells so the two never clash
+        let tile_size = data.tileSize as f32 * scale_factor;
+        let cells = (data.mapWidth * data.mapHeight) as usize;
+        let mut add_collider = |mut coll: BoxCollider, kind: ColliderKind| {
+            coll.id = ColliderId::Tile(cells + colliders.len());
+            coll.kind = kind;
+            grid.insert(&coll);
+            colliders.push(coll);
+        };
+        for (map, kind) in [(&one_way_map, ColliderKind::OneWay), (&hazard_map, ColliderKind::Hazard)] {
+            for rect in map.merge_rects() {
+                add_collider(rect_collider(&rect, data.mapWidth, data.mapHeight, tile_size), kind);
+            }
+        }
+        for rect in custom_rects {
+            add_collider(area_collider(rect, data.mapWidth, data.mapHeight, tile_size), ColliderKind::Solid);
+        }
+        //slopes are never merged, each tile gets its own collider
+        for (rect, slope) in slopes {
+            let mut coll = area_collider(rect, data.mapWidth, data.mapHeight, tile_size);
+            coll.slope = Some(slope);
+            add_collider(coll, ColliderKind::Solid);
+        }
+
+        let checkpoints = data.checkpoints.iter()
+            .map(|checkpoint| area_collider([checkpoint.x as f32, checkpoint.y as f32, 1.0, 1.0], data.mapWidth, data.mapHeight, tile_size))
+            .collect();
+        let pickups = data.pickups.iter()
+            .map(|pickup| (area_collider([pickup.x as f32, pickup.y as f32, 1.0, 1.0], data.mapWidth, data.mapHeight, tile_size), pickup.ability))
+            .collect();
+
+        Self {
+            colliders,
+            grid,
+            solid,
+            map_width: data.mapWidth,
+            map_height: data.mapHeight,
+            tile_size,
+            checkpoints,
+            pickups,
+        }
+    }
+
+    //draws every layer of the map into one image using the tiles in tileset_path and saves it to image_path
+    pub fn bake_image(json_path: &str, tileset_path: &str, image_path: &str) {
+        let data = read_map(json_path);
         //create a new image to write tile data to
         let mut newImage = ImageBuffer::<Rgba<u8>, Vec<u8>>::new(data.mapWidth * data.tileSize, data.mapHeight * data.tileSize);
-        let tileset = image::open("src/res/spritesheet.png").unwrap();
+        let tileset = image::open(tileset_path).unwrap();
         let tilesetWidth = tileset.width() / data.tileSize;
 
-        let mut colliders = Vec::new();
-
-        
-        
         //loop through all of the tiles in the tile map, and then add them to the new image
         for layer in data.layers.into_iter().rev() {
             for tile in layer.tiles {
@@ -71,20 +205,104 @@ impl TilesetManager {
                         
                     }
                 }
-
-                //create a collider for each tile in the tile map
-                let scale_factor = map_width as f32 / (data.mapWidth * data.tileSize) as f32;
-                let coll_pos = Vector2::new(-(xPos as f32 * data.tileSize as f32 * scale_factor - (data.mapWidth as f32 * data.tileSize as f32 * scale_factor / 2.0) as f32) - data.tileSize as f32 * scale_factor / 2.0, -(yPos as f32 * data.tileSize as f32 * scale_factor  - (data.mapHeight as f32 * data.tileSize as f32 * scale_factor / 2.0) as f32) - data.tileSize as f32 * scale_factor / 2.0);
-                let coll = BoxCollider::new(coll_pos, Vector2::new(data.tileSize as f32 * scale_factor, data.tileSize as f32 * scale_factor));
-                colliders.push(coll);
             }
         }
 
-        newImage.save("src/res/output.png").unwrap();
-        
+        newImage.save(image_path).unwrap();
+    }
 
-        Self {
-            colliders,
+    //world y below which anything has fallen out of the level, a tile's height under the bottom of the map
+    pub fn kill_plane(&self) -> f32 {
+        -(self.map_height as f32 + 2.0) * self.tile_size / 2.0
+    }
+
+    //changes whether tile (x, y) is a plain solid tile, the player collides with the change on the next tick
+    pub fn set_solid(&mut self, x: u32, y: u32, solid: bool) {
+        if x < self.map_width && y < self.map_height {
+            Tiles::set_solid_static(&mut self.solid, (x * self.map_height + y) as usize, solid);
+        }
+    }
+
+    //the solid bitset as terrain, laid out like every other collider of the map
+    fn solid_terrain(&self) -> TileGridTerrain<'_> {
+        let origin = Vector2::new(self.map_width as f32, self.map_height as f32) * self.tile_size / 2.0;
+        let mut terrain = TileGridTerrain::new(&self.solid, self.map_width as usize, self.map_height as usize, origin, self.tile_size);
+        terrain.flip_x = true;
+        terrain
+    }
+
+    //world space box covering tile (x, y)
+    pub fn tile_box(&self, x: u32, y: u32) -> BoxCollider {
+        area_collider([x as f32, y as f32, 1.0, 1.0], self.map_width, self.map_height, self.tile_size)
+    }
+}
+
+//read the json of the tilemap
+//using https://www.spritefusion.com/editor as tilemap editor
+fn read_map(json_path: &str) -> Tilemap {
+    let json_contents = fs::read_to_string(json_path).expect("Couldn't read JSON");
+    serde_json::from_str(&json_contents).unwrap()
+}
+
+impl Terrain for TilesetManager {
+    fn colliders_near(&self, min: Vector2<f32>, max: Vector2<f32>) -> Vec<BoxCollider> {
+        let mut colliders = self.solid_terrain().colliders_near(min, max);
+        colliders.extend(self.grid.query(min, max).into_iter().filter_map(|id| self.collider(id)));
+        colliders
+    }
+
+    fn collider(&self, id: ColliderId) -> Option<BoxCollider> {
+        let cells = (self.map_width * self.map_height) as usize;
+        match id {
+            ColliderId::Tile(i) if i < cells => self.solid_terrain().collider(id),
+            ColliderId::Tile(i) => self.colliders.get(i - cells).cloned(),
+            _ => None,
         }
     }
-}
\ No newline at end of file
+
+    //solid cells are walked through the bitset, only the other tiles are swept
+    fn raycast(&self, origin: Vector2<f32>, motion: Vector2<f32>, mask: Option<u32>) -> Option<RayHit> {
+        let point = BoxCollider::new(origin, Vector2::new(0.0, 0.0));
+        let others: Vec<BoxCollider> = self.grid.query(origin + motion.map(|m| m.min(0.0)), origin + motion.map(|m| m.max(0.0))).into_iter().filter_map(|id| self.collider(id)).collect();
+        [self.solid_terrain().raycast(origin, motion, mask), sweep_colliders(&point, motion, &others, mask)]
+            .into_iter()
+            .flatten()
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+//converts a rectangle of tiles into a world space collider
+//the map is centered on the origin, and tile x is flipped to match how the tileset sprite is drawn
+pub fn rect_collider(rect: &TileRect, map_width: u32, map_height: u32, tile_size: f32) -> BoxCollider {
+    area_collider([rect.x as f32, rect.y as f32, rect.width as f32, rect.height as f32], map_width, map_height, tile_size)
+}
+
+//same as rect_collider, but for [x, y, width, height] in fractional tiles
+pub fn area_collider(area: [f32; 4], map_width: u32, map_height: u32, tile_size: f32) -> BoxCollider {
+    let pos = Vector2::new(
+        -(area[0] * tile_size - map_width as f32 * tile_size / 2.0) - area[2] * tile_size / 2.0,
+        -(area[1] * tile_size - map_height as f32 * tile_size / 2.0) - area[3] * tile_size / 2.0,
+    );
+    BoxCollider::new(pos, Vector2::new(area[2] * tile_size, area[3] * tile_size))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+
+    use super::TilesetManager;
+
+    #[test]
+    fn test_tiles_outside_map_skipped() {
+        let path = env::temp_dir().join("tileset_test.json");
+        let path = path.to_str().unwrap();
+        //(1, 2) is one past the bottom of column 1, which is the top of column 2 in the bitset
+        fs::write(path, r#"{"tileSize":16,"mapWidth":3,"mapHeight":2,"layers":[{"name":"Layer_1","collider":true,"tiles":[
+            {"id":"0","x":0,"y":1},{"id":"0","x":1,"y":2},{"id":"0","x":3,"y":0},{"id":"0","x":40,"y":40}
+        ]}]}"#).unwrap();
+
+        let tileset = TilesetManager::new(path, 48);
+        let solid: Vec<(u32, u32)> = (0..3).flat_map(|x| (0..2).map(move |y| (x, y))).filter(|(x, y)| tileset.solid_terrain().is_solid(*x as i64, *y as i64)).collect();
+        assert_eq!(solid, vec![(0, 1)]);
+    }
+}
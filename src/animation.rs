@@ -0,0 +1,238 @@
+use std::collections::{HashMap, VecDeque};
+
+//the frames of a sprite sheet, each one is [x, y, width, height] in uv space (0 to 1) with y going down
+#[derive(Clone, Debug)]
+pub struct SpriteSheet {
+    pub frames: Vec<[f32; 4]>,
+}
+
+impl SpriteSheet {
+    //a sheet cut into equal sized frames, numbered left to right and then top to bottom
+    pub fn grid(columns: u32, rows: u32) -> Self {
+        let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+        let mut frames = vec![];
+        for y in 0..rows {
+            for x in 0..columns {
+                frames.push([x as f32 * width, y as f32 * height, width, height]);
+            }
+        }
+        Self { frames }
+    }
+
+    //frames given as [x, y, width, height] in pixels of a texture that is texture_size pixels big
+    pub fn from_pixels(rects: &[[u32; 4]], texture_size: [u32; 2]) -> Self {
+        let (w, h) = (texture_size[0] as f32, texture_size[1] as f32);
+        Self { frames: rects.iter().map(|rect| [rect[0] as f32 / w, rect[1] as f32 / h, rect[2] as f32 / w, rect[3] as f32 / h]).collect() }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlayMode {
+    Loop,
+    //stops on the last frame
+    Once,
+    //plays forwards then backwards forever
+    PingPong,
+}
+
+#[derive(Clone, Debug)]
+pub struct Clip {
+    //indices into the sprite sheet's frames
+    pub frames: Vec<usize>,
+    //how long each frame shows for in milliseconds, one per frame
+    pub durations: Vec<f32>,
+    pub mode: PlayMode,
+}
+
+impl Clip {
+    //every frame shows for frame_time milliseconds
+    pub fn new(frames: Vec<usize>, frame_time: f32, mode: PlayMode) -> Self {
+        let durations = vec![frame_time; frames.len()];
+        Self { frames, durations, mode }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AnimationEvent {
+    //a Once clip reached its last frame, or a Loop or PingPong clip came back to its first
+    Finished(String),
+}
+
+//plays clips by name, one at a time
+#[derive(Default)]
+pub struct Animator {
+    pub clips: HashMap<String, Clip>,
+    current: Option<String>,
+    //clips to play after the current one finishes
+    queue: VecDeque<String>,
+    //position in the current clip's frame list
+    step: usize,
+    //going backwards through a ping pong clip
+    reverse: bool,
+    //milliseconds the current frame has been showing
+    time: f32,
+    finished: bool,
+    events: Vec<AnimationEvent>,
+}
+
+impl Animator {
+    pub fn add_clip(&mut self, name: &str, clip: Clip) {
+        self.clips.insert(name.into(), clip);
+    }
+
+    //switches to the clip straight away and clears the queue
+    //playing the clip that is already running does nothing, a Once clip that has finished starts again
+    pub fn play(&mut self, name: &str) {
+        self.queue.clear();
+        if self.current.as_deref() == Some(name) && !self.finished {
+            return;
+        }
+        self.start(name);
+    }
+
+    //plays the clip once the current one finishes, or straight away if nothing is playing
+    pub fn queue(&mut self, name: &str) {
+        if self.current.is_none() || self.finished {
+            self.start(name);
+        } else {
+            self.queue.push_back(name.into());
+        }
+    }
+
+    fn start(&mut self, name: &str) {
+        self.current = self.clips.contains_key(name).then(|| name.into());
+        self.step = 0;
+        self.reverse = false;
+        self.time = 0.0;
+        self.finished = false;
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    //the sheet frame to show right now
+    pub fn frame(&self) -> Option<usize> {
+        let clip = self.clips.get(self.current.as_ref()?)?;
+        clip.frames.get(self.step).copied()
+    }
+
+    //events since the last call
+    pub fn take_events(&mut self) -> Vec<AnimationEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    //moves the current clip on by delta milliseconds
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+        loop {
+            let Some(name) = self.current.clone() else {
+                return;
+            };
+            //the clip was taken out of clips while it was playing
+            let Some(clip) = self.clips.get(&name) else {
+                self.current = None;
+                return;
+            };
+            let duration = clip.durations.get(self.step).copied().unwrap_or(0.0);
+            if self.finished || clip.frames.is_empty() || self.time < duration || duration <= 0.0 {
+                return;
+            }
+            self.time -= duration;
+            let last = clip.frames.len() - 1;
+            let (mode, mut done) = (clip.mode, false);
+
+            match mode {
+                PlayMode::Loop => {
+                    done = self.step == last;
+                    self.step = if done { 0 } else { self.step + 1 };
+                },
+                PlayMode::Once => {
+                    if self.step == last {
+                        done = true;
+                        self.finished = true;
+                    } else {
+                        self.step += 1;
+                    }
+                },
+                PlayMode::PingPong => {
+                    if last == 0 {
+                        done = true;
+                    } else if self.reverse {
+                        self.step -= 1;
+                        if self.step == 0 {
+                            self.reverse = false;
+                            done = true;
+                        }
+                    } else {
+                        self.step += 1;
+                        if self.step == last {
+                            self.reverse = true;
+                        }
+                    }
+                },
+            }
+
+            if done {
+                self.events.push(AnimationEvent::Finished(name));
+                if let Some(next) = self.queue.pop_front() {
+                    //time left over from this clip goes into the next one
+                    let time = self.time;
+                    self.start(&next);
+                    self.time = time;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AnimationEvent, Animator, Clip, PlayMode, SpriteSheet};
+
+    #[test]
+    fn test_clips() {
+        let mut animator = Animator::default();
+        animator.add_clip("run", Clip::new(vec![0, 1, 2], 100.0, PlayMode::Loop));
+        animator.add_clip("bounce", Clip::new(vec![3, 4, 5], 100.0, PlayMode::PingPong));
+        animator.add_clip("land", Clip::new(vec![6, 7], 50.0, PlayMode::Once));
+
+        animator.play("bounce");
+        let mut frames = vec![animator.frame().unwrap()];
+        for _ in 0..5 {
+            animator.update(100.0);
+            frames.push(animator.frame().unwrap());
+        }
+        assert_eq!(frames, vec![3, 4, 5, 4, 3, 4]);
+        assert_eq!(animator.take_events(), vec![AnimationEvent::Finished("bounce".into())]);
+
+        animator.play("land");
+        animator.queue("run");
+        animator.update(50.0);
+        assert_eq!(animator.frame(), Some(7));
+        //finishing land moves straight on to run, and big steps skip over whole frames
+        animator.update(150.0);
+        assert_eq!(animator.current(), Some("run"));
+        assert_eq!(animator.frame(), Some(1));
+        assert_eq!(animator.take_events(), vec![AnimationEvent::Finished("land".into())]);
+
+        //a one shot clip restarts when played again after finishing, but not while it is still going
+        animator.play("land");
+        animator.update(50.0);
+        animator.play("land");
+        assert_eq!(animator.frame(), Some(7));
+        animator.update(50.0);
+        assert_eq!(animator.take_events(), vec![AnimationEvent::Finished("land".into())]);
+        animator.play("land");
+        assert_eq!(animator.frame(), Some(6));
+
+        //taking away the clip that is playing stops it
+        animator.clips.remove("land");
+        animator.update(50.0);
+        assert_eq!(animator.current(), None);
+        assert_eq!(animator.frame(), None);
+
+        let sheet = SpriteSheet::grid(4, 2);
+        assert_eq!(sheet.frames[5], [0.25, 0.5, 0.25, 0.5]);
+    }
+}
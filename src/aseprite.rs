@@ -0,0 +1,513 @@
+use std::{collections::HashMap, io::{Cursor, Read}};
+
+use anyhow::{bail, ensure, Context, Result};
+use flate2::read::ZlibDecoder;
+use image::{ImageFormat, Rgba, RgbaImage};
+
+use crate::animation::{Clip, PlayMode, SpriteSheet};
+
+//reads .aseprite files, following https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md
+//every frame is flattened into one image and the frames are laid out left to right in a sheet
+
+const HEADER_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+const CHUNK_SLICE: u16 = 0x2022;
+
+//a named rectangle drawn in aseprite, used for hitboxes and pivot points
+#[derive(Clone, PartialEq, Debug)]
+pub struct Slice {
+    pub name: String,
+    //each key applies from its frame until the next key
+    pub keys: Vec<SliceKey>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SliceKey {
+    pub frame: usize,
+    //[x, y, width, height] in pixels of the frame, y going down
+    pub rect: [i32; 4],
+    //relative to the rect's top left corner
+    pub pivot: Option<[i32; 2]>,
+}
+
+//name of the slice that marks the point a sprite is held from, its pivot or the middle of its rect
+pub const PIVOT_SLICE: &str = "pivot";
+
+impl SliceKey {
+    //the pivot in pixels of the frame, or the middle of the rect when it doesn't have one
+    pub fn pivot_point(&self) -> [f32; 2] {
+        match self.pivot {
+            Some(pivot) => [(self.rect[0] + pivot[0]) as f32, (self.rect[1] + pivot[1]) as f32],
+            None => [self.rect[0] as f32 + self.rect[2] as f32 / 2.0, self.rect[1] as f32 + self.rect[3] as f32 / 2.0],
+        }
+    }
+}
+
+impl Slice {
+    //the key that applies on frame
+    pub fn key(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys.iter().rev().find(|key| key.frame <= frame)
+    }
+}
+
+pub struct Aseprite {
+    pub frame_size: [u32; 2],
+    //every frame flattened, left to right
+    pub image: RgbaImage,
+    pub sheet: SpriteSheet,
+    //one clip per tag
+    pub clips: HashMap<String, Clip>,
+    pub slices: Vec<Slice>,
+}
+
+struct Layer {
+    visible: bool,
+    opacity: u8,
+    child_level: u16,
+}
+
+struct Cel {
+    layer: usize,
+    x: i32,
+    y: i32,
+    opacity: u8,
+    z_index: i16,
+    width: u32,
+    height: u32,
+    //pixels in the file's color depth
+    data: Vec<u8>,
+}
+
+impl Aseprite {
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        reader.dword()?;
+        ensure!(reader.word()? == HEADER_MAGIC, "not an aseprite file");
+        let frame_count = reader.word()? as usize;
+        let (width, height) = (reader.word()? as u32, reader.word()? as u32);
+        let depth = reader.word()?;
+        ensure!(matches!(depth, 32 | 16 | 8), "unknown color depth {depth}");
+        let layer_opacity_valid = reader.dword()? & 1 != 0;
+        reader.skip(10)?;
+        let transparent_index = reader.byte()?;
+        reader.seek(128)?;
+
+        let mut layers: Vec<Layer> = vec![];
+        let mut palette = vec![[0_u8; 4]; 256];
+        let mut frames: Vec<Vec<Cel>> = vec![];
+        let mut durations = vec![];
+        let mut clips = HashMap::new();
+        let mut slices = vec![];
+
+        for frame in 0..frame_count {
+            let frame_start = reader.pos;
+            let frame_size = reader.dword()? as usize;
+            ensure!(reader.word()? == FRAME_MAGIC, "bad frame header on frame {frame}");
+            let old_chunks = reader.word()? as u32;
+            durations.push(reader.word()? as f32);
+            reader.skip(2)?;
+            let new_chunks = reader.dword()?;
+            let chunks = if new_chunks == 0 { old_chunks } else { new_chunks };
+
+            let mut cels = vec![];
+            for _ in 0..chunks {
+                let chunk_start = reader.pos;
+                let chunk_size = reader.dword()? as usize;
+                let chunk_type = reader.word()?;
+                match chunk_type {
+                    CHUNK_LAYER => {
+                        let flags = reader.word()?;
+                        reader.skip(2)?;
+                        let child_level = reader.word()?;
+                        reader.skip(6)?;
+                        let opacity = reader.byte()?;
+                        layers.push(Layer { visible: flags & 1 != 0, opacity: if layer_opacity_valid { opacity } else { 255 }, child_level });
+                    },
+                    CHUNK_CEL => {
+                        let layer = reader.word()? as usize;
+                        let (x, y) = (reader.short()? as i32, reader.short()? as i32);
+                        let opacity = reader.byte()?;
+                        let cel_type = reader.word()?;
+                        let z_index = reader.short()?;
+                        reader.skip(5)?;
+                        match cel_type {
+                            0 | 2 => {
+                                let (width, height) = (reader.word()? as u32, reader.word()? as u32);
+                                //the pixels are the rest of the chunk
+                                let remaining = (chunk_start + chunk_size).checked_sub(reader.pos).context("cel chunk is smaller than its header")?;
+                                let mut data = reader.bytes(remaining)?.to_vec();
+                                if cel_type == 2 {
+                                    let mut inflated = vec![];
+                                    ZlibDecoder::new(data.as_slice()).read_to_end(&mut inflated).context("couldn't decompress cel")?;
+                                    data = inflated;
+                                }
+                                cels.push(Cel { layer, x, y, opacity, z_index, width, height, data });
+                            },
+                            //linked cels reuse the pixels of the same layer on an earlier frame
+                            1 => {
+                                let linked = reader.word()? as usize;
+                                let source = frames.get(linked).and_then(|cels: &Vec<Cel>| cels.iter().find(|cel| cel.layer == layer));
+                                if let Some(source) = source {
+                                    cels.push(Cel { layer, x, y, opacity, z_index, width: source.width, height: source.height, data: source.data.clone() });
+                                }
+                            },
+                            //tilemaps aren't supported
+                            _ => {},
+                        }
+                    },
+                    CHUNK_TAGS => {
+                        let count = reader.word()?;
+                        reader.skip(8)?;
+                        for _ in 0..count {
+                            let (from, to) = (reader.word()? as usize, reader.word()? as usize);
+                            let direction = reader.byte()?;
+                            let repeat = reader.word()?;
+                            reader.skip(10)?;
+                            let name = reader.string()?;
+                            let mut frames: Vec<usize> = (from..=to.min(frame_count.saturating_sub(1))).collect();
+                            //reverse and ping pong reverse start from the last frame
+                            if direction == 1 || direction == 3 {
+                                frames.reverse();
+                            }
+                            let mode = match (direction, repeat) {
+                                (2 | 3, _) => PlayMode::PingPong,
+                                (_, 1) => PlayMode::Once,
+                                _ => PlayMode::Loop,
+                            };
+                            //durations are filled in once every frame has been read
+                            clips.insert(name, Clip { frames, durations: vec![], mode });
+                        }
+                    },
+                    CHUNK_PALETTE => {
+                        reader.dword()?;
+                        let (first, last) = (reader.dword()? as usize, reader.dword()? as usize);
+                        reader.skip(8)?;
+                        for i in first..=last {
+                            let flags = reader.word()?;
+                            let color = [reader.byte()?, reader.byte()?, reader.byte()?, reader.byte()?];
+                            if let Some(entry) = palette.get_mut(i) {
+                                *entry = color;
+                            }
+                            if flags & 1 != 0 {
+                                reader.string()?;
+                            }
+                        }
+                    },
+                    CHUNK_SLICE => {
+                        let key_count = reader.dword()?;
+                        let flags = reader.dword()?;
+                        reader.dword()?;
+                        let name = reader.string()?;
+                        let mut keys = vec![];
+                        for _ in 0..key_count {
+                            let frame = reader.dword()? as usize;
+                            let rect = [reader.long()?, reader.long()?, reader.dword()? as i32, reader.dword()? as i32];
+                            //nine patch centers aren't used
+                            if flags & 1 != 0 {
+                                reader.skip(16)?;
+                            }
+                            let pivot = if flags & 2 != 0 { Some([reader.long()?, reader.long()?]) } else { None };
+                            keys.push(SliceKey { frame, rect, pivot });
+                        }
+                        slices.push(Slice { name, keys });
+                    },
+                    _ => {},
+                }
+                reader.seek(chunk_start + chunk_size)?;
+            }
+            frames.push(cels);
+            reader.seek(frame_start + frame_size)?;
+        }
+
+        for clip in clips.values_mut() {
+            clip.durations = clip.frames.iter().map(|frame| durations[*frame]).collect();
+        }
+
+        let visible = visible_layers(&layers);
+        let mut image = RgbaImage::new(width * frame_count as u32, height);
+        for (i, cels) in frames.iter_mut().enumerate() {
+            //layers are drawn bottom to top, z index moves a cel up or down past other layers
+            cels.sort_by_key(|cel| (cel.layer as i32 + cel.z_index as i32, cel.z_index));
+            for cel in cels.iter() {
+                let Some(layer) = layers.get(cel.layer) else {
+                    continue;
+                };
+                if !visible[cel.layer] {
+                    continue;
+                }
+                let opacity = cel.opacity as u32 * layer.opacity as u32 / 255;
+                draw_cel(&mut image, cel, i as u32 * width, [width, height], opacity, depth, &palette, transparent_index)?;
+            }
+        }
+
+        let frame_rects: Vec<[u32; 4]> = (0..frame_count as u32).map(|i| [i * width, 0, width, height]).collect();
+        Ok(Self {
+            frame_size: [width, height],
+            sheet: SpriteSheet::from_pixels(&frame_rects, [image.width(), image.height()]),
+            image,
+            clips,
+            slices,
+        })
+    }
+
+    //the flattened frames as a png, which is what textures are made from
+    pub fn png(&self) -> Result<Vec<u8>> {
+        let mut bytes = Cursor::new(vec![]);
+        self.image.write_to(&mut bytes, ImageFormat::Png)?;
+        Ok(bytes.into_inner())
+    }
+}
+
+//a layer only shows if it and every group it is inside are visible
+fn visible_layers(layers: &[Layer]) -> Vec<bool> {
+    //visibility of the group at each child level above the current layer
+    let mut parents: Vec<bool> = vec![];
+    layers.iter().map(|layer| {
+        parents.truncate(layer.child_level as usize);
+        let visible = layer.visible && parents.iter().all(|parent| *parent);
+        parents.push(layer.visible);
+        visible
+    }).collect()
+}
+
+//blends a cel over the frame that starts at offset_x in the sheet
+fn draw_cel(image: &mut RgbaImage, cel: &Cel, offset_x: u32, frame_size: [u32; 2], opacity: u32, depth: u16, palette: &[[u8; 4]], transparent_index: u8) -> Result<()> {
+    let bytes_per_pixel = depth as usize / 8;
+    ensure!(cel.data.len() >= (cel.width * cel.height) as usize * bytes_per_pixel, "cel is missing pixels");
+    for y in 0..cel.height {
+        for x in 0..cel.width {
+            let (frame_x, frame_y) = (cel.x + x as i32, cel.y + y as i32);
+            if frame_x < 0 || frame_y < 0 || frame_x as u32 >= frame_size[0] || frame_y as u32 >= frame_size[1] {
+                continue;
+            }
+            let i = (y * cel.width + x) as usize * bytes_per_pixel;
+            let pixel = &cel.data[i..i + bytes_per_pixel];
+            let color = match depth {
+                32 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+                16 => [pixel[0], pixel[0], pixel[0], pixel[1]],
+                8 if pixel[0] == transparent_index => continue,
+                8 => palette[pixel[0] as usize],
+                _ => bail!("unknown color depth {depth}"),
+            };
+            let dst = image.get_pixel_mut(offset_x + frame_x as u32, frame_y as u32);
+            *dst = blend(*dst, color, opacity);
+        }
+    }
+    Ok(())
+}
+
+//normal blending of src over dst, with src's alpha scaled by opacity out of 255
+fn blend(dst: Rgba<u8>, src: [u8; 4], opacity: u32) -> Rgba<u8> {
+    let src_a = src[3] as u32 * opacity / 255;
+    if src_a == 0 {
+        return dst;
+    }
+    let dst_a = dst.0[3] as u32;
+    let out_a = src_a + dst_a * (255 - src_a) / 255;
+    let mut out = [0_u8; 4];
+    for c in 0..3 {
+        out[c] = ((src[c] as u32 * src_a + dst.0[c] as u32 * dst_a * (255 - src_a) / 255) / out_a) as u8;
+    }
+    out[3] = out_a as u8;
+    Rgba(out)
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl <'b> Reader<'b> {
+    fn bytes(&mut self, count: usize) -> Result<&'b [u8]> {
+        let bytes = self.bytes.get(self.pos..self.pos + count).context("aseprite file ended early")?;
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<()> {
+        self.bytes(count).map(|_| ())
+    }
+
+    fn seek(&mut self, pos: usize) -> Result<()> {
+        ensure!(pos <= self.bytes.len(), "aseprite file ended early");
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn word(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn short(&mut self) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn dword(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn long(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let length = self.word()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use crate::animation::PlayMode;
+
+    use super::Aseprite;
+
+    fn chunk(kind: u16, body: &[u8]) -> Vec<u8> {
+        let mut chunk = ((body.len() + 6) as u32).to_le_bytes().to_vec();
+        chunk.extend(kind.to_le_bytes());
+        chunk.extend(body);
+        chunk
+    }
+
+    fn frame(duration: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut frame = ((body.len() + 16) as u32).to_le_bytes().to_vec();
+        frame.extend(0xF1FA_u16.to_le_bytes());
+        frame.extend((chunks.len() as u16).to_le_bytes());
+        frame.extend(duration.to_le_bytes());
+        frame.extend([0; 2]);
+        frame.extend((chunks.len() as u32).to_le_bytes());
+        frame.extend(body);
+        frame
+    }
+
+    fn string(text: &str) -> Vec<u8> {
+        let mut bytes = (text.len() as u16).to_le_bytes().to_vec();
+        bytes.extend(text.as_bytes());
+        bytes
+    }
+
+    //a 2x2 rgba cel at x, y, cel_type 0 stores it raw and 2 compresses it
+    fn cel(x: i16, y: i16, cel_type: u16, pixels: &[u8]) -> Vec<u8> {
+        let mut body = 0_u16.to_le_bytes().to_vec();
+        body.extend(x.to_le_bytes());
+        body.extend(y.to_le_bytes());
+        body.push(255);
+        body.extend(cel_type.to_le_bytes());
+        body.extend([0; 7]);
+        body.extend(2_u16.to_le_bytes());
+        body.extend(2_u16.to_le_bytes());
+        if cel_type == 2 {
+            let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+            encoder.write_all(pixels).unwrap();
+            body.extend(encoder.finish().unwrap());
+        } else {
+            body.extend(pixels);
+        }
+        chunk(0x2005, &body)
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut layer = 1_u16.to_le_bytes().to_vec();
+        layer.extend([0; 10]);
+        layer.push(255);
+        layer.extend([0; 3]);
+        layer.extend(string("body"));
+
+        let mut tags = 1_u16.to_le_bytes().to_vec();
+        tags.extend([0; 8]);
+        tags.extend(0_u16.to_le_bytes());
+        tags.extend(1_u16.to_le_bytes());
+        //reverse, play once
+        tags.push(1);
+        tags.extend(1_u16.to_le_bytes());
+        tags.extend([0; 10]);
+        tags.extend(string("attack"));
+
+        let mut slice = 1_u32.to_le_bytes().to_vec();
+        //has a pivot
+        slice.extend(2_u32.to_le_bytes());
+        slice.extend(0_u32.to_le_bytes());
+        slice.extend(string("hitbox"));
+        slice.extend(1_u32.to_le_bytes());
+        for value in [1_i32, 0, 3, 4, 1, 2] {
+            slice.extend(value.to_le_bytes());
+        }
+
+        let red = [255, 0, 0, 255].repeat(4);
+        let blue = [0, 0, 255, 255].repeat(4);
+        let frames = [
+            frame(100, &[chunk(0x2004, &layer), cel(0, 0, 0, &red), chunk(0x2018, &tags)]),
+            frame(50, &[cel(2, 2, 2, &blue), chunk(0x2022, &slice)]),
+        ].concat();
+
+        let mut header = ((frames.len() + 128) as u32).to_le_bytes().to_vec();
+        header.extend(0xA5E0_u16.to_le_bytes());
+        //2 frames of 4x4 at 32 bits per pixel, layer opacity valid
+        for value in [2_u16, 4, 4, 32] {
+            header.extend(value.to_le_bytes());
+        }
+        header.extend(1_u32.to_le_bytes());
+        header.resize(128, 0);
+        let bytes = [header, frames].concat();
+
+        let aseprite = Aseprite::parse(&bytes).unwrap();
+        assert_eq!(aseprite.frame_size, [4, 4]);
+        assert_eq!((aseprite.image.width(), aseprite.image.height()), (8, 4));
+        assert_eq!(aseprite.sheet.frames[1], [0.5, 0.0, 0.5, 1.0]);
+
+        //each cel lands in its own frame and the rest stays clear
+        assert_eq!(aseprite.image.get_pixel(1, 1).0, [255, 0, 0, 255]);
+        assert_eq!(aseprite.image.get_pixel(2, 2).0, [0, 0, 0, 0]);
+        assert_eq!(aseprite.image.get_pixel(7, 3).0, [0, 0, 255, 255]);
+        assert_eq!(aseprite.image.get_pixel(5, 1).0, [0, 0, 0, 0]);
+
+        let attack = &aseprite.clips["attack"];
+        assert_eq!(attack.frames, vec![1, 0]);
+        assert_eq!(attack.durations, vec![50.0, 100.0]);
+        assert_eq!(attack.mode, PlayMode::Once);
+
+        let hitbox = &aseprite.slices[0];
+        assert_eq!(hitbox.name, "hitbox");
+        assert!(hitbox.key(0).is_none());
+        assert_eq!(hitbox.key(1).unwrap().rect, [1, 0, 3, 4]);
+        assert_eq!(hitbox.key(1).unwrap().pivot, Some([1, 2]));
+        assert_eq!(hitbox.key(1).unwrap().pivot_point(), [2.0, 2.0]);
+    }
+
+    #[test]
+    fn test_bad_chunk_size() {
+        //a cel chunk that claims to be smaller than the header already read from it
+        let mut bad_cel = cel(0, 0, 0, &[0; 16]);
+        bad_cel[..4].copy_from_slice(&8_u32.to_le_bytes());
+        let frames = frame(100, &[bad_cel]);
+        let mut header = ((frames.len() + 128) as u32).to_le_bytes().to_vec();
+        header.extend(0xA5E0_u16.to_le_bytes());
+        for value in [1_u16, 2, 2, 32] {
+            header.extend(value.to_le_bytes());
+        }
+        header.resize(128, 0);
+        assert!(Aseprite::parse(&[header, frames].concat()).is_err());
+    }
+
+    #[test]
+    fn test_pickup_asset() {
+        let aseprite = Aseprite::parse(&std::fs::read("src/res/pickup.aseprite").unwrap()).unwrap();
+        assert_eq!(aseprite.frame_size, [16, 16]);
+        assert_eq!(aseprite.clips["spin"].frames, vec![0, 1, 2, 3]);
+        assert_eq!(aseprite.slices[0].key(0).unwrap().pivot_point(), [8.0, 8.0]);
+    }
+}
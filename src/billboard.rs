@@ -8,6 +8,10 @@ pub struct Billboard {
     model: Model,
     position: Vector3<f32>,
     rotation: Quaternion<f32>,
+    uv_rect: [f32; 4],
+    flip: [bool; 2],
+    tint: [f32; 4],
+    opacity: f32,
 }
 
 impl Billboard {
@@ -18,11 +22,15 @@ impl Billboard {
             Vertex { position: [size*width/2.0, size*-height/2.0, 0.0], tex_pos: [1.0, 1.0], normal: [0.0, 0.0, 0.0] },
             Vertex { position: [size*width/2.0, size*height/2.0, 0.0], tex_pos: [1.0, 0.0], normal: [0.0, 0.0, 0.0] },
         ];
-        let model = Model::new_instances(vertices, &[0_u16, 1, 2, 2, 1, 3], vec![Instance {position, rotation}], device);
+        let model = Model::new_instances(vertices, &[0_u16, 1, 2, 2, 1, 3], vec![Instance {position, rotation, ..Default::default()}], device);
         Self {
             model,
             position,
             rotation,
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            flip: [false; 2],
+            tint: [1.0; 4],
+            opacity: 1.0,
         }
     }
 
@@ -42,8 +50,37 @@ impl Billboard {
         self.create_instance(device);
     }
 
+    //which part of the texture to show, [x, y, width, height] in uv space
+    pub fn set_uv_rect(&mut self, uv_rect: [f32; 4], device: &Device) {
+        self.uv_rect = uv_rect;
+        self.create_instance(device);
+    }
+
+    //mirrors the texture along [x, y]
+    pub fn set_flip(&mut self, flip: [bool; 2], device: &Device) {
+        if flip != self.flip {
+            self.flip = flip;
+            self.create_instance(device);
+        }
+    }
+
+    //color the texture is multiplied by, white leaves it as it is
+    pub fn set_tint(&mut self, tint: [f32; 4], device: &Device) {
+        if tint != self.tint {
+            self.tint = tint;
+            self.create_instance(device);
+        }
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32, device: &Device) {
+        if opacity != self.opacity {
+            self.opacity = opacity;
+            self.create_instance(device);
+        }
+    }
+
     fn create_instance(&mut self, device: &Device) {
-        self.model.update_instances(vec![Instance {position: self.position, rotation: self.rotation}], device);
+        self.model.update_instances(vec![Instance {position: self.position, rotation: self.rotation, uv_rect: self.uv_rect, flip: self.flip, tint: self.tint, opacity: self.opacity}], device);
     }
 }
 
@@ -16,11 +16,16 @@ struct InstanceInput {
     @location(6) model_matrix_1: vec4<f32>,
     @location(7) model_matrix_2: vec4<f32>,
     @location(8) model_matrix_3: vec4<f32>,
+    @location(9) uv_rect: vec4<f32>,
+    @location(10) tint: vec4<f32>,
+    @location(11) flip: vec2<f32>,
+    @location(12) opacity: f32,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
 };
 
 @vertex
@@ -36,11 +41,14 @@ fn vs_main(
     );
     var out: VertexOutput;
     out.clip_position = camera * model_matrix * vec4<f32>(model.position, 1.0);
-    out.tex_coords = model.tex_coords;
+    //flipping mirrors the coords inside the rect so it works on sprite sheet frames
+    let tex_coords = mix(model.tex_coords, 1.0 - model.tex_coords, instance.flip);
+    out.tex_coords = instance.uv_rect.xy + tex_coords * instance.uv_rect.zw;
+    out.color = vec4<f32>(instance.tint.rgb, instance.tint.a * instance.opacity);
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_sprite, s_sprite, in.tex_coords);
+    return textureSample(t_sprite, s_sprite, in.tex_coords) * in.color;
 }
\ No newline at end of file
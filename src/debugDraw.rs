@@ -0,0 +1,234 @@
+use std::mem;
+
+use bespoke_engine::{binding::Descriptor, model::ToRaw};
+use bytemuck::{cast_slice, NoUninit};
+use cgmath::{InnerSpace, Vector2};
+use wgpu::{util::{BufferInitDescriptor, DeviceExt}, Buffer, BufferDescriptor, BufferUsages, Device, IndexFormat, Queue, RenderPass};
+
+use crate::{instance::Instance, physics::{boxCollider::{BoxCollider, ColliderKind, LAYER_BODY, LAYER_PLAYER, LAYER_TERRAIN, LAYER_TRIGGER}, query::{CastRecord, RayHit}, terrain::Terrain, world::PhysicsWorld}, player::Player, shaders::ShaderManager};
+
+//how thick the lines are in world units
+const LINE_WIDTH: f32 = 1.0;
+//how far in front of the sprites the overlay is drawn, towards the camera
+const DEPTH: f32 = -1.0;
+//how long contact normals are drawn
+const NORMAL_LENGTH: f32 = 15.0;
+//indices are u16, so the overlay stops adding lines once it runs out
+const MAX_VERTICES: usize = u16::MAX as usize;
+//each line is 4 vertices and 12 indices
+const MAX_INDICES: usize = MAX_VERTICES / 4 * 12;
+
+pub const COLOR_TERRAIN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+pub const COLOR_PLAYER: [f32; 4] = [0.0, 0.8, 1.0, 1.0];
+pub const COLOR_BODY: [f32; 4] = [1.0, 0.5, 0.0, 1.0];
+pub const COLOR_TRIGGER: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+pub const COLOR_HAZARD: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+pub const COLOR_NORMAL: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+pub const COLOR_RAY: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+pub const COLOR_RAY_HIT: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+#[repr(C)]
+#[derive(NoUninit, Copy, Clone)]
+pub struct DebugVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl Descriptor for DebugVertex {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<DebugVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+//color a collider is outlined with, picked by its collision layer
+pub fn layer_color(collider: &BoxCollider) -> [f32; 4] {
+    if collider.kind == ColliderKind::Hazard {
+        return COLOR_HAZARD;
+    }
+    if collider.is_trigger || collider.layer & LAYER_TRIGGER != 0 {
+        COLOR_TRIGGER
+    } else if collider.layer & LAYER_PLAYER != 0 {
+        COLOR_PLAYER
+    } else if collider.layer & LAYER_BODY != 0 {
+        COLOR_BODY
+    } else if collider.layer & LAYER_TERRAIN != 0 {
+        COLOR_TERRAIN
+    } else {
+        [0.5, 0.5, 0.5, 1.0]
+    }
+}
+
+//an overlay of lines showing what the physics sees, built up again every frame it is enabled
+pub struct DebugDraw {
+    pub enabled: bool,
+    vertices: Vec<DebugVertex>,
+    indices: Vec<u16>,
+    //made once big enough for the most the overlay can hold, and rewritten on every upload
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    instance_buffer: Buffer,
+    //how many indices the last upload wrote
+    index_count: u32,
+}
+
+impl DebugDraw {
+    pub fn new(device: &Device) -> Self {
+        let vertex_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Debug Vertex Buffer"),
+            size: (MAX_VERTICES * mem::size_of::<DebugVertex>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let index_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Debug Index Buffer"),
+            size: (MAX_INDICES * mem::size_of::<u16>()) as u64,
+            usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let instance_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Debug Instance Buffer"),
+            contents: &Instance::default().to_raw(),
+            usage: BufferUsages::VERTEX,
+        });
+        Self {
+            enabled: false,
+            vertices: vec![],
+            indices: vec![],
+            vertex_buffer,
+            index_buffer,
+            instance_buffer,
+            index_count: 0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        if !self.enabled {
+            self.index_count = 0;
+        }
+    }
+
+    //a line between two world positions, drawn as a thin quad
+    pub fn line(&mut self, start: Vector2<f32>, end: Vector2<f32>, color: [f32; 4]) {
+        if start == end || self.vertices.len() + 4 > MAX_VERTICES {
+            return;
+        }
+        let dir = (end - start).normalize();
+        let side = Vector2::new(-dir.y, dir.x) * LINE_WIDTH / 2.0;
+        let first = self.vertices.len() as u16;
+        for corner in [start - side, start + side, end - side, end + side] {
+            //sprites are drawn with world x along z and world y along y
+            self.vertices.push(DebugVertex { position: [DEPTH, corner.y, corner.x], color });
+        }
+        //both windings so it shows up whichever way the quad faces
+        self.indices.extend([0, 1, 2, 2, 1, 3, 0, 2, 1, 2, 3, 1].map(|i| first + i));
+    }
+
+    pub fn rect(&mut self, min: Vector2<f32>, max: Vector2<f32>, color: [f32; 4]) {
+        let corners = [min, Vector2::new(max.x, min.y), max, Vector2::new(min.x, max.y)];
+        for i in 0..4 {
+            self.line(corners[i], corners[(i + 1) % 4], color);
+        }
+    }
+
+    //outline of a collider, slopes get their sloped top instead of the full box
+    pub fn collider(&mut self, collider: &BoxCollider) {
+        let color = layer_color(collider);
+        let (min, max) = (collider.min(), collider.max());
+        if collider.slope.is_none() {
+            self.rect(min, max, color);
+            return;
+        }
+        let top_min = Vector2::new(min.x, collider.surface_at(min.x));
+        let top_max = Vector2::new(max.x, collider.surface_at(max.x));
+        self.line(min, Vector2::new(max.x, min.y), color);
+        self.line(Vector2::new(max.x, min.y), top_max, color);
+        self.line(top_max, top_min, color);
+        self.line(top_min, min, color);
+    }
+
+    //a ray from origin to where it stopped, with the normal of whatever it hit
+    pub fn ray(&mut self, origin: Vector2<f32>, end: Vector2<f32>, hit: Option<&RayHit>) {
+        match hit {
+            Some(hit) => {
+                self.line(origin, hit.point, COLOR_RAY_HIT);
+                self.line(hit.point, hit.point + hit.normal * NORMAL_LENGTH, COLOR_NORMAL);
+            },
+            None => self.line(origin, end, COLOR_RAY),
+        }
+    }
+
+    //a recorded cast, box casts also get the box drawn where it stopped
+    pub fn cast(&mut self, cast: &CastRecord) {
+        self.ray(cast.start, cast.end, cast.hit.as_ref());
+        if cast.size.x > 0.0 || cast.size.y > 0.0 {
+            let center = cast.hit.map_or(cast.end, |hit| hit.point);
+            let color = if cast.hit.is_some() { COLOR_RAY_HIT } else { COLOR_RAY };
+            self.rect(center - cast.size / 2.0, center + cast.size / 2.0, color);
+        }
+    }
+
+    //every collider between view_min and view_max, the player's collider and contact normals, and the casts the world recorded
+    pub fn physics(&mut self, terrain: &dyn Terrain, world: &PhysicsWorld, player: &Player, view_min: Vector2<f32>, view_max: Vector2<f32>) {
+        for collider in terrain.colliders_near(view_min, view_max) {
+            self.collider(&collider);
+        }
+        for collider in world.colliders_near(view_min, view_max) {
+            self.collider(collider);
+        }
+        for trigger in &world.triggers {
+            self.collider(trigger);
+        }
+
+        let collider = player.collider();
+        self.collider(collider);
+        for contact in &player.contacts {
+            //normals point away from what was touched, so start them on the side of the player touching it
+            let start = collider.pos - Vector2::new(contact.normal.x * collider.size().x, contact.normal.y * collider.size().y) / 2.0;
+            self.line(start, start + contact.normal * NORMAL_LENGTH, COLOR_NORMAL);
+        }
+
+        for cast in world.take_casts() {
+            self.cast(&cast);
+        }
+    }
+
+    //writes everything drawn since the last upload into the buffers and starts over
+    pub fn upload(&mut self, queue: &Queue) {
+        if !self.indices.is_empty() {
+            queue.write_buffer(&self.vertex_buffer, 0, cast_slice(&self.vertices));
+            queue.write_buffer(&self.index_buffer, 0, cast_slice(&self.indices));
+        }
+        self.index_count = self.indices.len() as u32;
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    pub fn render<'s: 'b, 'b>(&'s self, render_pass: &mut RenderPass<'b>, shader_man: * mut ShaderManager) {
+        if self.index_count == 0 {
+            return;
+        }
+        unsafe {
+            (*shader_man).bind_shader("debug".into(), render_pass);
+        }
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint16);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+    }
+}
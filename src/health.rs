@@ -0,0 +1,73 @@
+//hit points for anything that can get hurt, with a moment of invulnerability after each hit
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+    //milliseconds left before the next hit can land
+    invulnerable: f32,
+}
+
+impl Health {
+    pub fn new(max: i32) -> Self {
+        Self {
+            current: max,
+            max,
+            invulnerable: 0.0,
+        }
+    }
+
+    pub fn tick(&mut self, delta: f32) {
+        self.invulnerable = (self.invulnerable - delta).max(0.0);
+    }
+
+    pub fn invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+
+    //milliseconds left before the next hit can land
+    pub fn invulnerable_time(&self) -> f32 {
+        self.invulnerable
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+
+    //takes amount off unless still invulnerable from the last hit, then stays invulnerable for invulnerable_time
+    //returns whether the hit landed
+    pub fn damage(&mut self, amount: i32, invulnerable_time: f32) -> bool {
+        if self.invulnerable() || self.is_dead() {
+            return false;
+        }
+        self.current = (self.current - amount).max(0);
+        self.invulnerable = invulnerable_time;
+        true
+    }
+
+    pub fn heal(&mut self, amount: i32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+
+    pub fn reset(&mut self) {
+        self.current = self.max;
+        self.invulnerable = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Health;
+
+    #[test]
+    fn test_invulnerability() {
+        let mut health = Health::new(3);
+        assert!(health.damage(1, 100.0));
+        //still flashing from the first hit
+        assert!(!health.damage(1, 100.0));
+        assert_eq!(health.current, 2);
+
+        health.tick(100.0);
+        assert!(health.damage(5, 100.0));
+        assert!(health.is_dead());
+        assert_eq!(health.current, 0);
+    }
+}
@@ -0,0 +1,177 @@
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, fs, io::ErrorKind};
+
+use serde::{Deserialize, Serialize};
+use winit::keyboard::KeyCode;
+
+pub const BINDINGS_PATH: &str = "src/res/bindings.json";
+
+//everything the game can be told to do, game code only ever looks at these and never at keys
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveDown,
+    Jump,
+    Dash,
+    ToggleDebug,
+}
+
+//how far each action is pushed on one tick, from 0 to 1, actions that aren't pushed at all are left out
+pub type ActionValues = BTreeMap<Action, f32>;
+
+//which keys trigger which actions, keys are named the same as winit's KeyCode variants
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bindings(pub HashMap<Action, Vec<String>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self(HashMap::from([
+            (Action::MoveLeft, vec!["KeyA".into(), "ArrowLeft".into()]),
+            (Action::MoveRight, vec!["KeyD".into(), "ArrowRight".into()]),
+            (Action::MoveDown, vec!["KeyS".into(), "ArrowDown".into()]),
+            (Action::Jump, vec!["KeyW".into(), "ArrowUp".into(), "Space".into()]),
+            (Action::Dash, vec!["ShiftLeft".into()]),
+            (Action::ToggleDebug, vec!["F3".into()]),
+        ]))
+    }
+}
+
+impl Bindings {
+    //a missing file falls back to the default bindings, one that can't be read or parsed is reported first
+    pub fn load(path: &str) -> Self {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                println!("couldn't load {path}: {err}");
+                return Self::default();
+            },
+        };
+        match serde_json::from_str(&contents) {
+            Ok(bindings) => bindings,
+            Err(err) => {
+                println!("couldn't load {path}: {err}");
+                Self::default()
+            },
+        }
+    }
+}
+
+//turns raw key events into action values
+pub struct InputMap {
+    //key name to the actions it is bound to
+    keys: HashMap<String, Vec<Action>>,
+    keys_down: Vec<KeyCode>,
+    //actions pressed since the last sample, so a tap shorter than a tick isn't lost
+    tapped: BTreeSet<Action>,
+}
+
+impl InputMap {
+    pub fn new(bindings: &Bindings) -> Self {
+        let mut keys: HashMap<String, Vec<Action>> = HashMap::new();
+        for (action, names) in &bindings.0 {
+            for name in names {
+                keys.entry(name.clone()).or_default().push(*action);
+            }
+        }
+        Self {
+            keys,
+            keys_down: vec![],
+            tapped: BTreeSet::new(),
+        }
+    }
+
+    fn actions(&self, code: &KeyCode) -> &[Action] {
+        self.keys.get(&format!("{code:?}")).map_or(&[], |actions| actions.as_slice())
+    }
+
+    pub fn key_event(&mut self, code: KeyCode, pressed: bool) {
+        if pressed {
+            if !self.keys_down.contains(&code) {
+                self.keys_down.push(code);
+                self.tapped.extend(self.actions(&code).to_vec());
+            }
+        } else if let Some(i) = self.keys_down.iter().position(|x| x == &code) {
+            self.keys_down.remove(i);
+        }
+    }
+
+    //the action values for the next tick
+    pub fn sample(&mut self) -> ActionValues {
+        let mut values: ActionValues = self.tapped.iter().map(|action| (*action, 1.0)).collect();
+        for code in &self.keys_down {
+            for action in self.actions(code) {
+                values.insert(*action, 1.0);
+            }
+        }
+        self.tapped.clear();
+        values
+    }
+}
+
+//the actions on this tick and the one before, which is what pressed, just pressed and just released are worked out from
+#[derive(Default)]
+pub struct ActionState {
+    current: ActionValues,
+    previous: ActionValues,
+}
+
+impl ActionState {
+    //moves on to the next tick
+    pub fn advance(&mut self, values: ActionValues) {
+        self.previous = std::mem::replace(&mut self.current, values);
+    }
+
+    pub fn value(&self, action: Action) -> f32 {
+        self.current.get(&action).copied().unwrap_or(0.0)
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.value(action) > 0.0
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed(action) && !self.previous.get(&action).is_some_and(|value| *value > 0.0)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        !self.pressed(action) && self.previous.get(&action).is_some_and(|value| *value > 0.0)
+    }
+
+    //-1 to 1, from how far negative and positive are pushed
+    pub fn axis(&self, negative: Action, positive: Action) -> f32 {
+        (self.value(positive) - self.value(negative)).clamp(-1.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use winit::keyboard::KeyCode;
+
+    use super::{Action, ActionState, Bindings, InputMap};
+
+    #[test]
+    fn test_actions() {
+        let mut input = InputMap::new(&Bindings::default());
+        let mut actions = ActionState::default();
+
+        input.key_event(KeyCode::ArrowLeft, true);
+        input.key_event(KeyCode::Space, true);
+        actions.advance(input.sample());
+        assert_eq!(actions.axis(Action::MoveLeft, Action::MoveRight), -1.0);
+        assert!(actions.just_pressed(Action::Jump));
+
+        actions.advance(input.sample());
+        assert!(actions.pressed(Action::Jump) && !actions.just_pressed(Action::Jump));
+
+        //a tap that starts and ends between two ticks still shows up for one tick
+        input.key_event(KeyCode::Space, false);
+        input.key_event(KeyCode::ShiftLeft, true);
+        input.key_event(KeyCode::ShiftLeft, false);
+        actions.advance(input.sample());
+        assert!(actions.just_released(Action::Jump));
+        assert!(actions.just_pressed(Action::Dash));
+        actions.advance(input.sample());
+        assert!(actions.just_released(Action::Dash));
+    }
+}
@@ -6,17 +6,24 @@ use cgmath::{Deg, Quaternion, Rotation3, Vector3};
 pub struct Instance {
     pub position: cgmath::Vector3<f32>,
     pub rotation: cgmath::Quaternion<f32>,
+    //part of the texture to show, [x, y, width, height] in uv space
+    pub uv_rect: [f32; 4],
+    //mirrors the texture along x and y
+    pub flip: [bool; 2],
+    //multiplied with the texture color
+    pub tint: [f32; 4],
+    pub opacity: f32,
 }
 
 impl Instance {
     pub fn raw(&self) -> InstanceRaw {
-        InstanceRaw {model: (cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)).into() }
+        InstanceRaw {model: (cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)).into(), uv_rect: self.uv_rect, tint: self.tint, flip: self.flip.map(|flip| if flip { 1.0 } else { 0.0 }), opacity: self.opacity }
     }
 }
 
 impl Default for Instance {
     fn default() -> Self {
-        Self { position: Vector3::new(0.0, 0.0, 0.0), rotation: Quaternion::from_axis_angle(Vector3::unit_z(), Deg(0.0)) }
+        Self { position: Vector3::new(0.0, 0.0, 0.0), rotation: Quaternion::from_axis_angle(Vector3::unit_z(), Deg(0.0)), uv_rect: [0.0, 0.0, 1.0, 1.0], flip: [false; 2], tint: [1.0; 4], opacity: 1.0 }
     }
 }
 
@@ -24,12 +31,16 @@ impl Default for Instance {
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug)]
 pub struct InstanceRaw {
     model: [[f32; 4]; 4],
+    uv_rect: [f32; 4],
+    tint: [f32; 4],
+    //1 for flipped, 0 for not
+    flip: [f32; 2],
+    opacity: f32,
 }
 
 impl ToRaw for Instance {
     fn to_raw(&self) -> Vec<u8> {
-        let raw = InstanceRaw {model: (cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)).into() };
-        bytes_of(&raw).to_vec()
+        bytes_of(&self.raw()).to_vec()
     }
 }
 
@@ -67,6 +78,26 @@ impl Descriptor for Instance {
                     shader_location: 8,
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 20]>() as wgpu::BufferAddress,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 24]>() as wgpu::BufferAddress,
+                    shader_location: 11,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 26]>() as wgpu::BufferAddress,
+                    shader_location: 12,
+                    format: wgpu::VertexFormat::Float32,
+                },
             ],
         }
     }
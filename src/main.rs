@@ -10,6 +10,15 @@ mod tiles;
 mod TilesetManager;
 mod physics;
 mod player;
+mod prop;
+mod debugDraw;
+mod save;
+mod input;
+mod replay;
+mod simulation;
+mod health;
+mod animation;
+mod aseprite;
 
 use crate::window::Window;
 use std::env;
@@ -1 +1,11 @@
-pub mod boxCollider;
\ No newline at end of file
+pub mod body;
+pub mod boxCollider;
+pub mod broadphase;
+pub mod colliderGen;
+pub mod kinematic;
+pub mod query;
+pub mod terrain;
+pub mod tileGrid;
+pub mod tileShape;
+pub mod trigger;
+pub mod world;
\ No newline at end of file
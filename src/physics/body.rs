@@ -0,0 +1,38 @@
+use cgmath::Vector2;
+
+use super::boxCollider::BoxCollider;
+
+//a box that is moved by gravity and collisions, like a crate
+pub struct RigidBody {
+    pub collider: BoxCollider,
+    //world units per tick, with y going up
+    pub vel: Vector2<f32>,
+    pub mass: f32,
+    //fraction of horizontal speed lost each tick while resting on something
+    pub friction: f32,
+    //fraction of speed kept when bouncing off something, 0 doesn't bounce at all
+    pub restitution: f32,
+    pub gravity_scale: f32,
+    pub grounded: bool,
+    //pos at the start of the last tick, rendering blends between this and the collider's pos
+    pub prev_pos: Vector2<f32>,
+}
+
+impl RigidBody {
+    pub fn new(pos: Vector2<f32>, size: Vector2<f32>, mass: f32) -> Self {
+        Self {
+            collider: BoxCollider::new(pos, size),
+            vel: Vector2::new(0.0, 0.0),
+            mass,
+            friction: 0.2,
+            restitution: 0.0,
+            gravity_scale: 1.0,
+            grounded: false,
+            prev_pos: pos,
+        }
+    }
+
+    pub fn render_pos(&self, alpha: f32) -> Vector2<f32> {
+        self.prev_pos + (self.collider.pos - self.prev_pos) * alpha
+    }
+}
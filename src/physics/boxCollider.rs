@@ -1,11 +1,80 @@
 use cgmath::Vector2;
 
+//how far apart two colliders can be and still count as touching
+pub const CONTACT_TOLERANCE: f32 = 0.05;
 
+//collision layers, a collider is on the layers in its layer bits and only interacts with colliders on the layers in its mask
+pub const LAYER_TERRAIN: u32 = 1 << 0;
+pub const LAYER_PLAYER: u32 = 1 << 1;
+pub const LAYER_BODY: u32 = 1 << 2;
+pub const LAYER_TRIGGER: u32 = 1 << 3;
+pub const LAYER_ALL: u32 = u32::MAX;
 
+//identifies a collider and what owns it
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum ColliderId {
+    //not registered with anything yet
+    None,
+    //index of a tile in the terrain, what it means depends on the Terrain backend
+    Tile(usize),
+    //index into PhysicsWorld::bodies
+    Body(usize),
+    //index into PhysicsWorld::triggers
+    Trigger(usize),
+    //index into PhysicsWorld::kinematics
+    Kinematic(usize),
+    Player,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColliderKind {
+    Solid,
+    //solid only when landed on from above
+    OneWay,
+    //doesn't block anything, but hurts whatever touches it
+    Hazard,
+}
+
+impl ColliderKind {
+    //whether movement should stop at colliders of this kind
+    pub fn blocks(&self) -> bool {
+        match self {
+            ColliderKind::Solid | ColliderKind::OneWay => true,
+            ColliderKind::Hazard => false,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct BoxCollider {
     //pos of the center of the collider
     pub pos: Vector2<f32>,
     size: Vector2<f32>,
+    pub id: ColliderId,
+    pub kind: ColliderKind,
+    //makes the top of the box a slope, heights of the surface at min x and max x as a fraction of the height
+    pub slope: Option<[f32; 2]>,
+    pub layer: u32,
+    pub mask: u32,
+    //triggers never block anything, they only report what is inside them
+    pub is_trigger: bool,
+}
+
+pub struct SweepHit {
+    //fraction of the motion (0 to 1) that can be travelled before touching the other collider
+    pub time: f32,
+    //normal of the surface that was hit, pointing away from the other collider
+    pub normal: Vector2<f32>,
+    pub other: ColliderId,
+}
+
+pub struct Contact {
+    //how far the colliders overlap along the normal, 0 when they are only touching
+    pub depth: f32,
+    //direction to push this collider to get it out of the other one
+    pub normal: Vector2<f32>,
+    pub other: ColliderId,
+    pub kind: ColliderKind,
 }
 
 impl BoxCollider {
@@ -13,14 +82,55 @@ impl BoxCollider {
         Self {
             pos,
             size,
+            id: ColliderId::None,
+            kind: ColliderKind::Solid,
+            slope: None,
+            layer: LAYER_TERRAIN,
+            mask: LAYER_ALL,
+            is_trigger: false,
+        }
+    }
+
+    pub fn size(&self) -> Vector2<f32> {
+        self.size
+    }
+
+    pub fn set_size(&mut self, size: Vector2<f32>) {
+        self.size = size;
+    }
+
+    //bottom left corner
+    pub fn min(&self) -> Vector2<f32> {
+        self.pos - self.size/2.0
+    }
+
+    //top right corner
+    pub fn max(&self) -> Vector2<f32> {
+        self.pos + self.size/2.0
+    }
+
+    //world y of the top surface at x, which is only different from max().y for slopes
+    pub fn surface_at(&self, x: f32) -> f32 {
+        match self.slope {
+            Some([at_min, at_max]) => {
+                let t = ((x - self.min().x) / self.size.x).clamp(0.0, 1.0);
+                self.min().y + (at_min + (at_max - at_min) * t) * self.size.y
+            },
+            None => self.max().y,
         }
     }
 
+    //highest point of the top surface between min_x and max_x
+    pub fn highest_surface(&self, min_x: f32, max_x: f32) -> f32 {
+        //the surface is a straight line, so the highest point is at one of the ends
+        self.surface_at(min_x.max(self.min().x)).max(self.surface_at(max_x.min(self.max().x)))
+    }
+
     pub fn CheckCollision(&mut self, other: &BoxCollider) -> bool {
         let top_left = self.pos - self.size/2.0;
         let other_top_left = other.pos - other.size/2.0;
 
-        let collisionX = top_left.x + self.size.x >= other_top_left.x && 
+        let collisionX = top_left.x + self.size.x >= other_top_left.x &&
         other_top_left.x + other.size.x >= top_left.x;
         // collision y-axis?
         let collisionY = top_left.y + self.size.y >= other_top_left.y &&
@@ -28,4 +138,147 @@ impl BoxCollider {
         // collision only if on both axes
         return collisionX && collisionY;
     }
+
+    //both colliders have to have the other's layer in their mask
+    pub fn interacts(&self, other: &BoxCollider) -> bool {
+        self.mask & other.layer != 0 && other.mask & self.layer != 0
+    }
+
+    //whether the colliders overlap by more than just touching
+    pub fn overlaps(&self, other: &BoxCollider) -> bool {
+        let offset = self.pos - other.pos;
+        let half = (self.size + other.size) / 2.0;
+        offset.x.abs() < half.x && offset.y.abs() < half.y
+    }
+
+    //whether this collider stops mover from moving by motion
+    //one way colliders only stop things coming down onto them from above
+    pub fn blocks(&self, mover: &BoxCollider, motion: Vector2<f32>) -> bool {
+        if self.is_trigger || !self.interacts(mover) {
+            return false;
+        }
+        match self.kind {
+            ColliderKind::Solid => true,
+            ColliderKind::OneWay => motion.y < 0.0 && mover.min().y >= self.max().y - CONTACT_TOLERANCE,
+            ColliderKind::Hazard => false,
+        }
+    }
+
+    //returns how this collider touches other, if at all
+    //the normal is along the axis with the least overlap, so a box resting on a floor gets an upwards normal
+    pub fn contact(&self, other: &BoxCollider) -> Option<Contact> {
+        let half = (self.size + other.size) / 2.0;
+        let offset = self.pos - other.pos;
+        let overlap = Vector2::new(half.x - offset.x.abs(), half.y - offset.y.abs());
+
+        //boxes that only meet at a corner aren't touching
+        if overlap.x < -CONTACT_TOLERANCE || overlap.y < -CONTACT_TOLERANCE || overlap.x.max(overlap.y) <= 0.0 {
+            return None;
+        }
+
+        let (depth, normal) = if overlap.x < overlap.y {
+            (overlap.x, Vector2::new(if offset.x < 0.0 { -1.0 } else { 1.0 }, 0.0))
+        } else {
+            (overlap.y, Vector2::new(0.0, if offset.y < 0.0 { -1.0 } else { 1.0 }))
+        };
+
+        Some(Contact { depth: depth.max(0.0), normal, other: other.id, kind: other.kind })
+    }
+
+    //moves this collider along motion and returns when and where it first touches other
+    //this is a ray cast of the center against the other box grown by our size, so fast movement can't skip over thin colliders
+    pub fn sweep(&self, motion: Vector2<f32>, other: &BoxCollider) -> Option<SweepHit> {
+        let half = (self.size + other.size) / 2.0;
+        let min = other.pos - half;
+        let max = other.pos + half;
+
+        let mut entry = f32::NEG_INFINITY;
+        let mut exit = f32::INFINITY;
+        let mut normal = Vector2::new(0.0, 0.0);
+
+        for axis in 0..2 {
+            if motion[axis] == 0.0 {
+                //not moving on this axis, so we can only hit if we are already inside the slab
+                if self.pos[axis] <= min[axis] || self.pos[axis] >= max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (min[axis] - self.pos[axis]) / motion[axis];
+            let t2 = (max[axis] - self.pos[axis]) / motion[axis];
+            let near = t1.min(t2);
+            let far = t1.max(t2);
+
+            if near > entry {
+                entry = near;
+                normal = Vector2::new(0.0, 0.0);
+                normal[axis] = -motion[axis].signum();
+            }
+            exit = exit.min(far);
+        }
+
+        //already overlapping, missing or too far away this frame
+        if entry > exit || entry < 0.0 || entry > 1.0 || exit <= 0.0 {
+            return None;
+        }
+
+        Some(SweepHit { time: entry, normal, other: other.id })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::Vector2;
+
+    use super::{BoxCollider, ColliderId};
+
+    #[test]
+    fn test_sweep_stops_at_floor() {
+        let player = BoxCollider::new(Vector2::new(0.0, 100.0), Vector2::new(50.0, 50.0));
+        let floor = BoxCollider::new(Vector2::new(0.0, 0.0), Vector2::new(16.0, 16.0));
+        //fast enough to skip over the whole floor in one step
+        let hit = player.sweep(Vector2::new(0.0, -500.0), &floor).unwrap();
+        assert_eq!(hit.normal, Vector2::new(0.0, 1.0));
+        assert!((100.0 - 500.0 * hit.time - 33.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_sweep_slides_along_floor() {
+        let player = BoxCollider::new(Vector2::new(0.0, 33.0), Vector2::new(50.0, 50.0));
+        let floor = BoxCollider::new(Vector2::new(20.0, 0.0), Vector2::new(16.0, 16.0));
+        assert!(player.sweep(Vector2::new(30.0, 0.0), &floor).is_none());
+    }
+
+    #[test]
+    fn test_contact_normals() {
+        let mut floor = BoxCollider::new(Vector2::new(0.0, 0.0), Vector2::new(16.0, 16.0));
+        floor.id = ColliderId::Tile(7);
+
+        let above = BoxCollider::new(Vector2::new(5.0, 32.99), Vector2::new(50.0, 50.0));
+        let contact = above.contact(&floor).unwrap();
+        assert_eq!(contact.normal, Vector2::new(0.0, 1.0));
+        assert_eq!(contact.other, ColliderId::Tile(7));
+
+        let below = BoxCollider::new(Vector2::new(5.0, -30.0), Vector2::new(50.0, 50.0));
+        let contact = below.contact(&floor).unwrap();
+        assert_eq!(contact.normal, Vector2::new(0.0, -1.0));
+        assert!((contact.depth - 3.0).abs() < 0.001);
+
+        let corner = BoxCollider::new(Vector2::new(33.0, 33.0), Vector2::new(50.0, 50.0));
+        assert!(corner.contact(&floor).is_none());
+    }
+
+    #[test]
+    fn test_slope_surface() {
+        //45 degree slope rising towards +x
+        let mut slope = BoxCollider::new(Vector2::new(8.0, 8.0), Vector2::new(16.0, 16.0));
+        slope.slope = Some([0.0, 1.0]);
+        assert_eq!(slope.surface_at(0.0), 0.0);
+        assert_eq!(slope.surface_at(4.0), 4.0);
+        assert_eq!(slope.surface_at(40.0), 16.0);
+        //a player standing across the top end is held up by the highest point
+        assert_eq!(slope.highest_surface(10.0, 60.0), 16.0);
+        assert_eq!(slope.highest_surface(-20.0, 6.0), 6.0);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use cgmath::Vector2;
+
+use super::boxCollider::{BoxCollider, ColliderId};
+
+//uniform grid that buckets colliders by the cells they cover, so a query only looks at colliders near it
+//cells are normally one tile big
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<ColliderId>>,
+    //the range of cells each collider was inserted into, so it can be removed without searching every cell
+    ranges: HashMap<ColliderId, ((i32, i32), (i32, i32))>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            ranges: HashMap::new(),
+        }
+    }
+
+    fn cell_range(&self, min: Vector2<f32>, max: Vector2<f32>) -> ((i32, i32), (i32, i32)) {
+        let start = ((min.x / self.cell_size).floor() as i32, (min.y / self.cell_size).floor() as i32);
+        let end = ((max.x / self.cell_size).floor() as i32, (max.y / self.cell_size).floor() as i32);
+        (start, end)
+    }
+
+    pub fn insert(&mut self, collider: &BoxCollider) {
+        if self.ranges.contains_key(&collider.id) {
+            self.remove(collider.id);
+        }
+
+        let (start, end) = self.cell_range(collider.min(), collider.max());
+        for x in start.0..=end.0 {
+            for y in start.1..=end.1 {
+                self.cells.entry((x, y)).or_default().push(collider.id);
+            }
+        }
+        self.ranges.insert(collider.id, (start, end));
+    }
+
+    pub fn remove(&mut self, id: ColliderId) {
+        if let Some((start, end)) = self.ranges.remove(&id) {
+            for x in start.0..=end.0 {
+                for y in start.1..=end.1 {
+                    if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                        cell.retain(|other| *other != id);
+                        if cell.is_empty() {
+                            self.cells.remove(&(x, y));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    //call after a collider moves, only touches the cells if it crossed into different ones
+    pub fn update(&mut self, collider: &BoxCollider) {
+        let range = self.cell_range(collider.min(), collider.max());
+        if self.ranges.get(&collider.id) != Some(&range) {
+            self.insert(collider);
+        }
+    }
+
+    //ids of every collider in a cell overlapping the area, each id only appears once
+    pub fn query(&self, min: Vector2<f32>, max: Vector2<f32>) -> Vec<ColliderId> {
+        let (start, end) = self.cell_range(min, max);
+        let mut found = Vec::new();
+        for x in start.0..=end.0 {
+            for y in start.1..=end.1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    found.extend_from_slice(cell);
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::Vector2;
+
+    use crate::physics::boxCollider::{BoxCollider, ColliderId};
+
+    use super::SpatialGrid;
+
+    #[test]
+    fn test_insert_move_remove() {
+        let mut grid = SpatialGrid::new(16.0);
+        let mut coll = BoxCollider::new(Vector2::new(8.0, 8.0), Vector2::new(16.0, 16.0));
+        coll.id = ColliderId::Tile(3);
+        grid.insert(&coll);
+        assert_eq!(grid.query(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0)), vec![ColliderId::Tile(3)]);
+        assert!(grid.query(Vector2::new(100.0, 100.0), Vector2::new(101.0, 101.0)).is_empty());
+
+        coll.pos = Vector2::new(108.0, 108.0);
+        grid.update(&coll);
+        assert!(grid.query(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0)).is_empty());
+        assert_eq!(grid.query(Vector2::new(100.0, 100.0), Vector2::new(101.0, 101.0)), vec![ColliderId::Tile(3)]);
+
+        grid.remove(ColliderId::Tile(3));
+        assert!(grid.query(Vector2::new(100.0, 100.0), Vector2::new(101.0, 101.0)).is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//turns a grid of solid tiles into as few colliders as possible
+//everything here is in tile coordinates, x going right and y going down like in the map editor
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TileRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EdgeSide {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+//a run of tile edges that all face open space on the same side, from start to end in tile corner coordinates
+//a floor made of many tiles becomes a single Top edge, so nothing can catch on the seams between tiles
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EdgeChain {
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+    pub side: EdgeSide,
+}
+
+pub struct SolidMap {
+    pub width: u32,
+    pub height: u32,
+    solid: Vec<bool>,
+}
+
+impl SolidMap {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            solid: vec![false; (width * height) as usize],
+        }
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, solid: bool) {
+        if x < self.width && y < self.height {
+            self.solid[(y * self.width + x) as usize] = solid;
+        }
+    }
+
+    //anything outside the map counts as open space
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return false;
+        }
+        self.solid[(y as u32 * self.width + x as u32) as usize]
+    }
+
+    //greedily grows rectangles right and then down from the first unused solid tile, row by row
+    pub fn merge_rects(&self) -> Vec<TileRect> {
+        let mut used = vec![false; self.solid.len()];
+        let free = |used: &Vec<bool>, x: u32, y: u32| self.get(x as i64, y as i64) && !used[(y * self.width + x) as usize];
+
+        let mut rects = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !free(&used, x, y) {
+                    continue;
+                }
+
+                let mut width = 1;
+                while x + width < self.width && free(&used, x + width, y) {
+                    width += 1;
+                }
+
+                let mut height = 1;
+                while y + height < self.height && (x..x + width).all(|rx| free(&used, rx, y + height)) {
+                    height += 1;
+                }
+
+                for ry in y..y + height {
+                    for rx in x..x + width {
+                        used[(ry * self.width + rx) as usize] = true;
+                    }
+                }
+                rects.push(TileRect { x, y, width, height });
+            }
+        }
+        rects
+    }
+
+    //every exposed tile edge, with collinear neighbours joined into one chain
+    pub fn edge_chains(&self) -> Vec<EdgeChain> {
+        let mut chains = Vec::new();
+
+        for y in 0..self.height {
+            for (side, dy, edge_y) in [(EdgeSide::Top, -1, y), (EdgeSide::Bottom, 1, y + 1)] {
+                let mut start = None;
+                for x in 0..=self.width {
+                    let exposed = x < self.width && self.get(x as i64, y as i64) && !self.get(x as i64, y as i64 + dy);
+                    match (exposed, start) {
+                        (true, None) => start = Some(x),
+                        (false, Some(sx)) => {
+                            chains.push(EdgeChain { start: (sx, edge_y), end: (x, edge_y), side });
+                            start = None;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        for x in 0..self.width {
+            for (side, dx, edge_x) in [(EdgeSide::Left, -1, x), (EdgeSide::Right, 1, x + 1)] {
+                let mut start = None;
+                for y in 0..=self.height {
+                    let exposed = y < self.height && self.get(x as i64, y as i64) && !self.get(x as i64 + dx, y as i64);
+                    match (exposed, start) {
+                        (true, None) => start = Some(y),
+                        (false, Some(sy)) => {
+                            chains.push(EdgeChain { start: (edge_x, sy), end: (edge_x, y), side });
+                            start = None;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        chains
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EdgeChain, EdgeSide, SolidMap, TileRect};
+
+    #[test]
+    fn test_merge_floor_and_wall() {
+        //a floor along row 3 with a wall going up from its left end
+        let mut map = SolidMap::new(6, 4);
+        for x in 0..6 {
+            map.set(x, 3, true);
+        }
+        for y in 0..3 {
+            map.set(0, y, true);
+        }
+
+        let rects = map.merge_rects();
+        assert_eq!(rects, vec![
+            TileRect { x: 0, y: 0, width: 1, height: 4 },
+            TileRect { x: 1, y: 3, width: 5, height: 1 },
+        ]);
+
+        let chains = map.edge_chains();
+        assert!(chains.contains(&EdgeChain { start: (1, 3), end: (6, 3), side: EdgeSide::Top }));
+        assert!(chains.contains(&EdgeChain { start: (0, 4), end: (6, 4), side: EdgeSide::Bottom }));
+    }
+}
@@ -0,0 +1,148 @@
+use cgmath::{InnerSpace, Vector2};
+
+use super::boxCollider::{BoxCollider, ColliderId};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PathMode {
+    //goes through the waypoints once and stops at the last one
+    Linear,
+    //goes back and forth along the waypoints
+    PingPong,
+    //goes around the waypoints, from the last one back to the first
+    Loop,
+}
+
+//a collider that moves along a path no matter what is in the way, like a moving platform
+//it shoves whatever it runs into and carries whatever stands on it
+pub struct KinematicBody {
+    pub collider: BoxCollider,
+    pub waypoints: Vec<Vector2<f32>>,
+    pub mode: PathMode,
+    //world units per tick
+    pub speed: f32,
+    //inactive platforms wait where they are until activate is called or activated_by is entered
+    pub active: bool,
+    pub activated_by: Option<ColliderId>,
+    //how far the platform moved in the last tick
+    pub delta: Vector2<f32>,
+    pub prev_pos: Vector2<f32>,
+    target: usize,
+    forward: bool,
+}
+
+impl KinematicBody {
+    //panics without any waypoints, a platform that stays put still needs the one it sits at
+    pub fn new(waypoints: Vec<Vector2<f32>>, size: Vector2<f32>, mode: PathMode, speed: f32) -> Self {
+        assert!(!waypoints.is_empty(), "a kinematic body needs at least one waypoint");
+        let start = waypoints[0];
+        Self {
+            collider: BoxCollider::new(start, size),
+            target: 1.min(waypoints.len() - 1),
+            waypoints,
+            mode,
+            speed,
+            active: true,
+            activated_by: None,
+            delta: Vector2::new(0.0, 0.0),
+            prev_pos: start,
+            forward: true,
+        }
+    }
+
+    pub fn activate(&mut self) {
+        self.active = true;
+    }
+
+    pub fn render_pos(&self, alpha: f32) -> Vector2<f32> {
+        self.prev_pos + (self.collider.pos - self.prev_pos) * alpha
+    }
+
+    //moves speed along the path, carrying on to the next waypoint if one is reached part way through the tick
+    pub fn advance(&mut self) {
+        self.prev_pos = self.collider.pos;
+        if !self.active {
+            self.delta = Vector2::new(0.0, 0.0);
+            return;
+        }
+
+        let mut remaining = self.speed;
+        while remaining > 0.0 {
+            let to_target = self.waypoints[self.target] - self.collider.pos;
+            let distance = to_target.magnitude();
+            if distance > remaining {
+                self.collider.pos += to_target / distance * remaining;
+                break;
+            }
+            self.collider.pos = self.waypoints[self.target];
+            remaining -= distance;
+            if !self.next_target() {
+                break;
+            }
+        }
+        self.delta = self.collider.pos - self.prev_pos;
+    }
+
+    //picks the waypoint to go to after reaching the current one, returns false if the path is finished
+    fn next_target(&mut self) -> bool {
+        let last = self.waypoints.len() - 1;
+        if last == 0 {
+            return false;
+        }
+        match self.mode {
+            PathMode::Linear => {
+                if self.target == last {
+                    return false;
+                }
+                self.target += 1;
+            },
+            PathMode::PingPong => {
+                if self.forward && self.target == last || !self.forward && self.target == 0 {
+                    self.forward = !self.forward;
+                }
+                self.target = if self.forward { self.target + 1 } else { self.target - 1 };
+            },
+            PathMode::Loop => self.target = (self.target + 1) % (last + 1),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::Vector2;
+
+    use super::{KinematicBody, PathMode};
+
+    #[test]
+    fn test_ping_pong() {
+        let mut platform = KinematicBody::new(vec![Vector2::new(0.0, 0.0), Vector2::new(10.0, 0.0)], Vector2::new(50.0, 10.0), PathMode::PingPong, 4.0);
+        platform.advance();
+        platform.advance();
+        assert_eq!(platform.collider.pos, Vector2::new(8.0, 0.0));
+        //reaches the end part way through the tick and turns around with the rest of the movement
+        platform.advance();
+        assert_eq!(platform.collider.pos, Vector2::new(8.0, 0.0));
+        assert_eq!(platform.delta, Vector2::new(0.0, 0.0));
+        platform.advance();
+        assert_eq!(platform.collider.pos, Vector2::new(4.0, 0.0));
+    }
+
+    #[test]
+    fn test_linear_stops_at_end() {
+        let mut platform = KinematicBody::new(vec![Vector2::new(0.0, 0.0), Vector2::new(0.0, 5.0)], Vector2::new(50.0, 10.0), PathMode::Linear, 4.0);
+        platform.active = false;
+        platform.advance();
+        assert_eq!(platform.collider.pos, Vector2::new(0.0, 0.0));
+        platform.activate();
+        platform.advance();
+        platform.advance();
+        platform.advance();
+        assert_eq!(platform.collider.pos, Vector2::new(0.0, 5.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one waypoint")]
+    fn test_empty_path() {
+        KinematicBody::new(vec![], Vector2::new(50.0, 10.0), PathMode::Loop, 4.0);
+    }
+}
@@ -0,0 +1,183 @@
+use cgmath::{InnerSpace, Vector2};
+
+use super::{boxCollider::{BoxCollider, ColliderId}, terrain::Terrain, world::PhysicsWorld};
+
+//what a ray or cast ran into
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RayHit {
+    //where the ray hit, or where the center of the box was when it touched for box casts
+    pub point: Vector2<f32>,
+    pub normal: Vector2<f32>,
+    pub distance: f32,
+    pub collider: ColliderId,
+}
+
+//a ray or box cast made while the world was recording casts, kept for the debug overlay
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CastRecord {
+    pub start: Vector2<f32>,
+    pub end: Vector2<f32>,
+    //size of the box that was cast, zero for rays
+    pub size: Vector2<f32>,
+    pub hit: Option<RayHit>,
+}
+
+//what a ray walking a tile grid ran into, in tile coordinates
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GridHit {
+    pub cell: (i64, i64),
+    pub point: Vector2<f32>,
+    pub normal: Vector2<f32>,
+    pub distance: f32,
+}
+
+//casts a ray against the terrain and bodies, mask picks which collision layers it can hit (None hits everything)
+//rays starting inside a collider don't hit it, and slopes are treated as their full box
+pub fn raycast(terrain: &dyn Terrain, world: &PhysicsWorld, origin: Vector2<f32>, dir: Vector2<f32>, max_distance: f32, mask: Option<u32>) -> Option<RayHit> {
+    if dir.magnitude2() == 0.0 || max_distance <= 0.0 || !max_distance.is_finite() {
+        return None;
+    }
+    let motion = dir.normalize() * max_distance;
+    let point = BoxCollider::new(origin, Vector2::new(0.0, 0.0));
+    let (min, max) = (origin + motion.map(|m| m.min(0.0)), origin + motion.map(|m| m.max(0.0)));
+    //the terrain gets to walk its own cells, bodies are swept like any other cast
+    let hit = [terrain.raycast(origin, motion, mask), sweep_colliders(&point, motion, world.colliders_near(min, max), mask)]
+        .into_iter()
+        .flatten()
+        .min_by(|a, b| a.distance.total_cmp(&b.distance));
+    world.record_cast(CastRecord { start: origin, end: origin + motion, size: point.size(), hit });
+    hit
+}
+
+pub fn segment_cast(terrain: &dyn Terrain, world: &PhysicsWorld, start: Vector2<f32>, end: Vector2<f32>, mask: Option<u32>) -> Option<RayHit> {
+    raycast(terrain, world, start, end - start, (end - start).magnitude(), mask)
+}
+
+//moves collider along motion and returns the first thing it would touch
+pub fn box_cast(terrain: &dyn Terrain, world: &PhysicsWorld, collider: &BoxCollider, motion: Vector2<f32>, mask: Option<u32>) -> Option<RayHit> {
+    if motion.magnitude2() == 0.0 {
+        return None;
+    }
+
+    let min = collider.min() + motion.map(|m| m.min(0.0));
+    let max = collider.max() + motion.map(|m| m.max(0.0));
+    let hit = sweep_colliders(collider, motion, terrain.colliders_near(min, max).iter().chain(world.colliders_near(min, max)), mask);
+    world.record_cast(CastRecord { start: collider.pos, end: collider.pos + motion, size: collider.size(), hit });
+    hit
+}
+
+//the first of others that collider would touch moving along motion, skipping itself, triggers and anything mask leaves out
+pub fn sweep_colliders<'c>(collider: &BoxCollider, motion: Vector2<f32>, others: impl IntoIterator<Item = &'c BoxCollider>, mask: Option<u32>) -> Option<RayHit> {
+    let distance = motion.magnitude();
+    others.into_iter()
+        .filter(|other_coll| other_coll.id != collider.id && !other_coll.is_trigger && mask.is_none_or(|mask| mask & other_coll.layer != 0))
+        .filter_map(|other_coll| collider.sweep(motion, other_coll))
+        .min_by(|a, b| a.time.total_cmp(&b.time))
+        .map(|hit| RayHit {
+            point: collider.pos + motion * hit.time,
+            normal: hit.normal,
+            distance: distance * hit.time,
+            collider: hit.other,
+        })
+}
+
+//walks a ray through a grid of unit cells one cell at a time until solid says a cell is filled
+//this only looks at the cells the ray passes through, so it is much cheaper than checking colliders for long rays
+//max_distance has to be finite, past the edge of a grid every cell is empty and the walk would never end
+pub fn grid_raycast(origin: Vector2<f32>, dir: Vector2<f32>, max_distance: f32, solid: impl Fn(i64, i64) -> bool) -> Option<GridHit> {
+    if dir.magnitude2() == 0.0 || !max_distance.is_finite() {
+        return None;
+    }
+    let dir = dir.normalize();
+
+    let mut cell = (origin.x.floor() as i64, origin.y.floor() as i64);
+    if solid(cell.0, cell.1) {
+        return Some(GridHit { cell, point: origin, normal: Vector2::new(0.0, 0.0), distance: 0.0 });
+    }
+
+    let step = (dir.x.signum() as i64, dir.y.signum() as i64);
+    //distance along the ray between crossing two vertical or two horizontal grid lines
+    let t_delta = Vector2::new(1.0 / dir.x.abs(), 1.0 / dir.y.abs());
+    //distance along the ray to the next vertical and horizontal grid lines
+    let mut t_max = Vector2::new(
+        if dir.x > 0.0 { (cell.0 as f32 + 1.0 - origin.x) / dir.x } else if dir.x < 0.0 { (origin.x - cell.0 as f32) / -dir.x } else { f32::INFINITY },
+        if dir.y > 0.0 { (cell.1 as f32 + 1.0 - origin.y) / dir.y } else if dir.y < 0.0 { (origin.y - cell.1 as f32) / -dir.y } else { f32::INFINITY },
+    );
+
+    loop {
+        let (distance, normal) = if t_max.x < t_max.y {
+            cell.0 += step.0;
+            let distance = t_max.x;
+            t_max.x += t_delta.x;
+            (distance, Vector2::new(-step.0 as f32, 0.0))
+        } else {
+            cell.1 += step.1;
+            let distance = t_max.y;
+            t_max.y += t_delta.y;
+            (distance, Vector2::new(0.0, -step.1 as f32))
+        };
+
+        if distance > max_distance {
+            return None;
+        }
+        if solid(cell.0, cell.1) {
+            return Some(GridHit { cell, point: origin + dir * distance, normal, distance });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::Vector2;
+
+    use crate::physics::{boxCollider::BoxCollider, tileGrid::TileGridTerrain, world::PhysicsWorld};
+
+    use super::{box_cast, grid_raycast, raycast};
+
+    #[test]
+    fn test_grid_raycast() {
+        //a wall along x = 5
+        let solid = |x: i64, _y: i64| x == 5;
+
+        let hit = grid_raycast(Vector2::new(0.5, 0.5), Vector2::new(1.0, 0.0), 100.0, solid).unwrap();
+        assert_eq!(hit.cell, (5, 0));
+        assert_eq!(hit.normal, Vector2::new(-1.0, 0.0));
+        assert!((hit.distance - 4.5).abs() < 0.001);
+
+        let hit = grid_raycast(Vector2::new(0.5, 0.5), Vector2::new(1.0, 1.0), 100.0, solid).unwrap();
+        assert_eq!(hit.cell.0, 5);
+        assert!((hit.point.x - 5.0).abs() < 0.001);
+
+        assert!(grid_raycast(Vector2::new(0.5, 0.5), Vector2::new(-1.0, 0.0), 100.0, solid).is_none());
+        assert!(grid_raycast(Vector2::new(0.5, 0.5), Vector2::new(1.0, 0.0), 3.0, solid).is_none());
+        //an endless ray that never hits anything gives up instead of walking forever
+        assert!(grid_raycast(Vector2::new(0.5, 0.5), Vector2::new(-1.0, 0.0), f32::INFINITY, solid).is_none());
+        assert!(grid_raycast(Vector2::new(0.5, 0.5), Vector2::new(-1.0, 0.0), f32::NAN, solid).is_none());
+    }
+
+    #[test]
+    fn test_casts_recorded() {
+        //one solid 10 unit tile with its top left corner at the origin
+        let solid = vec![1_u32];
+        let terrain = TileGridTerrain::new(&solid, 1, 1, Vector2::new(0.0, 0.0), 10.0);
+        let mut world = PhysicsWorld::new(50.0);
+        let origin = Vector2::new(5.0, 20.0);
+
+        raycast(&terrain, &world, origin, Vector2::new(0.0, -1.0), 100.0, None);
+        assert!(world.take_casts().is_empty());
+
+        world.record_casts = true;
+        let hit = raycast(&terrain, &world, origin, Vector2::new(0.0, -1.0), 100.0, None);
+        let box_hit = box_cast(&terrain, &world, &BoxCollider::new(origin, Vector2::new(4.0, 4.0)), Vector2::new(20.0, 0.0), None);
+        let casts = world.take_casts();
+        assert_eq!(casts.len(), 2);
+        assert_eq!(casts[0].hit, hit);
+        assert!((casts[0].hit.unwrap().point.y - 0.0).abs() < 0.001);
+        assert_eq!(casts[0].end, Vector2::new(5.0, -80.0));
+        assert_eq!(casts[1].size, Vector2::new(4.0, 4.0));
+        assert_eq!(casts[1].hit, box_hit);
+        assert!(box_hit.is_none());
+        //taking the casts empties the log
+        assert!(world.take_casts().is_empty());
+    }
+}
@@ -0,0 +1,19 @@
+use cgmath::Vector2;
+
+use super::{boxCollider::{BoxCollider, ColliderId}, query::{sweep_colliders, RayHit}};
+
+//the static collision of a level, which the player, bodies and queries all move through
+//colliders are handed out by value so a backend can build them on the fly instead of storing them
+pub trait Terrain {
+    //every collider that could be touching the area between min and max
+    fn colliders_near(&self, min: Vector2<f32>, max: Vector2<f32>) -> Vec<BoxCollider>;
+    fn collider(&self, id: ColliderId) -> Option<BoxCollider>;
+
+    //the first collider a ray from origin along motion hits, a ray starting inside a collider doesn't hit it
+    //by default this sweeps a point through colliders_near, grids can walk their cells instead
+    fn raycast(&self, origin: Vector2<f32>, motion: Vector2<f32>, mask: Option<u32>) -> Option<RayHit> {
+        let point = BoxCollider::new(origin, Vector2::new(0.0, 0.0));
+        let colliders = self.colliders_near(origin + motion.map(|m| m.min(0.0)), origin + motion.map(|m| m.max(0.0)));
+        sweep_colliders(&point, motion, &colliders, mask)
+    }
+}
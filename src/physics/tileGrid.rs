@@ -0,0 +1,164 @@
+use cgmath::{InnerSpace, Vector2};
+
+use super::{boxCollider::{BoxCollider, ColliderId, LAYER_TERRAIN}, query::{grid_raycast, RayHit}, terrain::Terrain};
+
+//collision read straight out of a packed solid bitset like the one Tiles keeps, one box per solid cell
+//the boxes are made when they are asked for and never stored, so a set_solid shows up in the very next query
+pub struct TileGridTerrain<'a> {
+    solid: &'a [u32],
+    width: usize,
+    height: usize,
+    //world position of the top left corner of tile (0, 0), tile y goes down the screen so it goes down in the world too
+    pub origin: Vector2<f32>,
+    pub tile_size: f32,
+    //tile x goes towards -x in the world, which is how TilesetManager lays out its maps
+    pub flip_x: bool,
+}
+
+impl <'a> TileGridTerrain<'a> {
+    //solid is indexed the same way as Tiles, x * height + y
+    pub fn new(solid: &'a [u32], width: usize, height: usize, origin: Vector2<f32>, tile_size: f32) -> Self {
+        Self { solid, width, height, origin, tile_size, flip_x: false }
+    }
+
+    pub fn is_solid(&self, x: i64, y: i64) -> bool {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return false;
+        }
+        let i = x as usize * self.height + y as usize;
+        self.solid[i / 32] >> (i % 32) & 1 == 1
+    }
+
+    //the tile a world position is in, which can be outside the map
+    pub fn cell_at(&self, pos: Vector2<f32>) -> (i64, i64) {
+        (((pos.x - self.origin.x) * self.x_sign() / self.tile_size).floor() as i64, ((self.origin.y - pos.y) / self.tile_size).floor() as i64)
+    }
+
+    fn x_sign(&self) -> f32 {
+        if self.flip_x { -1.0 } else { 1.0 }
+    }
+
+    fn cell_collider(&self, x: i64, y: i64) -> BoxCollider {
+        let pos = Vector2::new(self.origin.x + (x as f32 + 0.5) * self.tile_size * self.x_sign(), self.origin.y - (y as f32 + 0.5) * self.tile_size);
+        let mut collider = BoxCollider::new(pos, Vector2::new(self.tile_size, self.tile_size));
+        collider.id = ColliderId::Tile(x as usize * self.height + y as usize);
+        collider
+    }
+}
+
+impl <'a> Terrain for TileGridTerrain<'a> {
+    fn colliders_near(&self, min: Vector2<f32>, max: Vector2<f32>) -> Vec<BoxCollider> {
+        //world min y is the bottom, which is the highest tile y, and flipping x swaps the ends the same way
+        let (corner_x, max_y) = self.cell_at(min);
+        let (other_x, min_y) = self.cell_at(max);
+        let (min_x, max_x) = (corner_x.min(other_x), corner_x.max(other_x));
+        let mut colliders = Vec::new();
+        for x in min_x.max(0)..=max_x.min(self.width as i64 - 1) {
+            for y in min_y.max(0)..=max_y.min(self.height as i64 - 1) {
+                if self.is_solid(x, y) {
+                    colliders.push(self.cell_collider(x, y));
+                }
+            }
+        }
+        colliders
+    }
+
+    fn collider(&self, id: ColliderId) -> Option<BoxCollider> {
+        match id {
+            ColliderId::Tile(i) if i < self.width * self.height => {
+                let (x, y) = ((i / self.height) as i64, (i % self.height) as i64);
+                self.is_solid(x, y).then(|| self.cell_collider(x, y))
+            },
+            _ => None,
+        }
+    }
+
+    //walks the bitset with grid_raycast instead of building a box for every cell along the ray
+    fn raycast(&self, origin: Vector2<f32>, motion: Vector2<f32>, mask: Option<u32>) -> Option<RayHit> {
+        if mask.is_some_and(|mask| mask & LAYER_TERRAIN == 0) {
+            return None;
+        }
+        //in tiles, with x flipped along with the map and y going down
+        let start = Vector2::new((origin.x - self.origin.x) * self.x_sign(), self.origin.y - origin.y) / self.tile_size;
+        let dir = Vector2::new(motion.x * self.x_sign(), -motion.y);
+        let start_cell = self.cell_at(origin);
+        let hit = grid_raycast(start, dir, motion.magnitude() / self.tile_size, |x, y| (x, y) != start_cell && self.is_solid(x, y))?;
+        let distance = hit.distance * self.tile_size;
+        Some(RayHit {
+            point: origin + motion.normalize() * distance,
+            normal: Vector2::new(hit.normal.x * self.x_sign(), -hit.normal.y),
+            distance,
+            collider: self.cell_collider(hit.cell.0, hit.cell.1).id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{InnerSpace, Vector2};
+
+    use crate::physics::{boxCollider::{BoxCollider, ColliderId, LAYER_BODY}, query::sweep_colliders, terrain::Terrain};
+
+    use super::TileGridTerrain;
+
+    #[test]
+    fn test_tile_grid_follows_bitset() {
+        //4x4 map of 10 unit tiles with the top left corner at the origin
+        let (width, height) = (4, 4);
+        let mut solid = vec![0_u32; 1];
+        //floor along tile row 3
+        for x in 0..width {
+            let i = x * height + 3;
+            solid[i / 32] |= 1 << (i % 32);
+        }
+
+        let player = BoxCollider::new(Vector2::new(15.0, -5.0), Vector2::new(8.0, 8.0));
+        let motion = Vector2::new(0.0, -40.0);
+        let near = |terrain: &TileGridTerrain| terrain.colliders_near(player.min() + motion, player.max());
+
+        let terrain = TileGridTerrain::new(&solid, width, height, Vector2::new(0.0, 0.0), 10.0);
+        let hit = near(&terrain).iter().filter_map(|other| player.sweep(motion, other)).min_by(|a, b| a.time.total_cmp(&b.time)).unwrap();
+        assert_eq!(hit.other, ColliderId::Tile(height + 3));
+        assert!((player.pos.y + motion.y * hit.time - -26.0).abs() < 0.001);
+
+        //digging out the tile under the player lets it fall through without rebuilding anything
+        let i = height + 3;
+        solid[i / 32] &= !(1 << (i % 32));
+        let terrain = TileGridTerrain::new(&solid, width, height, Vector2::new(0.0, 0.0), 10.0);
+        assert!(near(&terrain).iter().all(|other| player.sweep(motion, other).is_none()));
+        assert!(terrain.collider(ColliderId::Tile(i)).is_none());
+        assert!(terrain.collider(ColliderId::Tile(2 * height + 3)).is_some());
+    }
+
+    #[test]
+    fn test_raycast_matches_colliders() {
+        //a wall along tile column 2 and a floor along row 3 of a 4x4 map, laid out both ways round
+        let (width, height) = (4, 4);
+        let mut solid = vec![0_u32; 1];
+        for i in (0..width).map(|x| x * height + 3).chain((0..height).map(|y| 2 * height + y)) {
+            solid[i / 32] |= 1 << (i % 32);
+        }
+        for flip_x in [false, true] {
+            let mut terrain = TileGridTerrain::new(&solid, width, height, Vector2::new(0.0, 0.0), 10.0);
+            terrain.flip_x = flip_x;
+            let sign = if flip_x { -1.0 } else { 1.0 };
+            for (origin, motion) in [
+                (Vector2::new(5.0 * sign, -5.0), Vector2::new(0.0, -100.0)),
+                (Vector2::new(5.0 * sign, -5.0), Vector2::new(100.0 * sign, 0.0)),
+                (Vector2::new(5.0 * sign, -5.0), Vector2::new(30.0 * sign, -20.0)),
+                //starting inside the wall doesn't hit it
+                (Vector2::new(25.0 * sign, -5.0), Vector2::new(0.0, -100.0)),
+            ] {
+                let point = BoxCollider::new(origin, Vector2::new(0.0, 0.0));
+                let colliders = terrain.colliders_near(origin + motion.map(|m| m.min(0.0)), origin + motion.map(|m| m.max(0.0)));
+                let swept = sweep_colliders(&point, motion, &colliders, None).unwrap();
+                let walked = terrain.raycast(origin, motion, None).unwrap();
+                assert_eq!(walked.collider, swept.collider);
+                assert_eq!(walked.normal, swept.normal);
+                assert!((walked.distance - swept.distance).abs() < 0.001);
+                assert!((walked.point - swept.point).magnitude() < 0.001);
+            }
+            assert!(terrain.raycast(Vector2::new(5.0 * sign, -5.0), Vector2::new(0.0, -100.0), Some(LAYER_BODY)).is_none());
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+//the collision shapes a single tile can have
+//heights and rects are fractions of the tile, and left and right are in map space (before the world flips x)
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum TileShape {
+    Full,
+    //bottom half of the tile
+    Half,
+    //top half of the tile, for ceilings
+    HalfTop,
+    //bottom quarter of the tile
+    Quarter,
+    //45 degree slopes, rising towards the right or the left
+    Slope45Right,
+    Slope45Left,
+    //2:1 slopes, about 26.6 degrees, which take two tiles to climb a full tile
+    //they stand in for 22.5 degree slopes, which don't meet the tile edges at whole or half heights
+    //the low tile goes from 0 to half height and the high tile from half to full
+    SlopeGentleRightLow,
+    SlopeGentleRightHigh,
+    SlopeGentleLeftLow,
+    SlopeGentleLeftHigh,
+    //any other slope, heights of the surface at the left and right edges of the tile
+    Slope { left: f32, right: f32 },
+}
+
+impl TileShape {
+    //the solid part of the tile as [x, y, width, height] with y going down, None for slopes
+    pub fn rect(&self) -> Option<[f32; 4]> {
+        match self {
+            TileShape::Full => Some([0.0, 0.0, 1.0, 1.0]),
+            TileShape::Half => Some([0.0, 0.5, 1.0, 0.5]),
+            TileShape::HalfTop => Some([0.0, 0.0, 1.0, 0.5]),
+            TileShape::Quarter => Some([0.0, 0.75, 1.0, 0.25]),
+            _ => None,
+        }
+    }
+
+    //height of the surface at the left and right edges of the tile, None for shapes that are just rects
+    pub fn slope(&self) -> Option<[f32; 2]> {
+        match *self {
+            TileShape::Slope45Right => Some([0.0, 1.0]),
+            TileShape::Slope45Left => Some([1.0, 0.0]),
+            TileShape::SlopeGentleRightLow => Some([0.0, 0.5]),
+            TileShape::SlopeGentleRightHigh => Some([0.5, 1.0]),
+            TileShape::SlopeGentleLeftLow => Some([0.5, 0.0]),
+            TileShape::SlopeGentleLeftHigh => Some([1.0, 0.5]),
+            TileShape::Slope { left, right } => Some([left, right]),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use super::boxCollider::ColliderId;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TriggerEventKind {
+    //other started overlapping the trigger this tick
+    Enter,
+    //other was already inside the trigger last tick and still is
+    Stay,
+    //other stopped overlapping the trigger this tick
+    Exit,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TriggerEvent {
+    pub kind: TriggerEventKind,
+    pub trigger: ColliderId,
+    pub other: ColliderId,
+}
@@ -0,0 +1,281 @@
+use std::{cell::RefCell, collections::BTreeSet};
+
+use cgmath::{InnerSpace, Vector2};
+
+
+use super::{body::RigidBody, kinematic::KinematicBody, boxCollider::{BoxCollider, ColliderId, SweepHit, LAYER_BODY, LAYER_TRIGGER}, broadphase::SpatialGrid, query::CastRecord, terrain::Terrain, trigger::{TriggerEvent, TriggerEventKind}};
+
+//world units per tick added to a body's downward speed every millisecond, same as the player's gravity
+pub const GRAVITY: f32 = 0.01;
+//gap kept between bodies and whatever they hit
+const SKIN: f32 = 0.01;
+//bounces slower than this are stopped so bodies come to rest
+const REST_SPEED: f32 = 0.05;
+
+//everything in the level that moves, the static terrain is passed in as a Terrain
+pub struct PhysicsWorld {
+    pub bodies: Vec<RigidBody>,
+    pub kinematics: Vec<KinematicBody>,
+    //bodies and kinematic bodies
+    grid: SpatialGrid,
+    pub triggers: Vec<BoxCollider>,
+    trigger_grid: SpatialGrid,
+    //(trigger, other) pairs that were overlapping at the end of the last tick
+    overlapping: BTreeSet<(ColliderId, ColliderId)>,
+    events: Vec<TriggerEvent>,
+    //when set every query cast is kept until take_casts, the debug overlay turns this on to draw them
+    pub record_casts: bool,
+    //queries only borrow the world, so the log has to be written through a RefCell
+    casts: RefCell<Vec<CastRecord>>,
+}
+
+impl PhysicsWorld {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            bodies: Vec::new(),
+            kinematics: Vec::new(),
+            grid: SpatialGrid::new(cell_size),
+            triggers: Vec::new(),
+            trigger_grid: SpatialGrid::new(cell_size),
+            overlapping: BTreeSet::new(),
+            events: Vec::new(),
+            record_casts: false,
+            casts: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn add_body(&mut self, mut body: RigidBody) -> ColliderId {
+        let id = ColliderId::Body(self.bodies.len());
+        body.collider.id = id;
+        body.collider.layer = LAYER_BODY;
+        self.grid.insert(&body.collider);
+        self.bodies.push(body);
+        id
+    }
+
+    pub fn add_kinematic(&mut self, mut kinematic: KinematicBody) -> ColliderId {
+        let id = ColliderId::Kinematic(self.kinematics.len());
+        kinematic.collider.id = id;
+        self.grid.insert(&kinematic.collider);
+        self.kinematics.push(kinematic);
+        id
+    }
+
+    pub fn kinematic(&self, id: ColliderId) -> Option<&KinematicBody> {
+        match id {
+            ColliderId::Kinematic(i) => self.kinematics.get(i),
+            _ => None,
+        }
+    }
+
+    //adds a collider that reports what overlaps it instead of blocking, its mask picks what it reacts to
+    pub fn add_trigger(&mut self, mut collider: BoxCollider) -> ColliderId {
+        let id = ColliderId::Trigger(self.triggers.len());
+        collider.id = id;
+        collider.layer = LAYER_TRIGGER;
+        collider.is_trigger = true;
+        self.trigger_grid.insert(&collider);
+        self.triggers.push(collider);
+        id
+    }
+
+    pub fn body(&self, id: ColliderId) -> Option<&RigidBody> {
+        match id {
+            ColliderId::Body(i) => self.bodies.get(i),
+            _ => None,
+        }
+    }
+
+    pub fn collider(&self, id: ColliderId) -> Option<&BoxCollider> {
+        match id {
+            ColliderId::Body(i) => self.bodies.get(i).map(|body| &body.collider),
+            ColliderId::Kinematic(i) => self.kinematics.get(i).map(|kinematic| &kinematic.collider),
+            ColliderId::Trigger(i) => self.triggers.get(i),
+            _ => None,
+        }
+    }
+
+    //where to draw a body or kinematic body between the last two ticks
+    pub fn render_pos(&self, id: ColliderId, alpha: f32) -> Option<Vector2<f32>> {
+        match id {
+            ColliderId::Body(i) => self.bodies.get(i).map(|body| body.render_pos(alpha)),
+            ColliderId::Kinematic(i) => self.kinematics.get(i).map(|kinematic| kinematic.render_pos(alpha)),
+            _ => None,
+        }
+    }
+
+    //colliders of bodies that could be touching the area between min and max
+    pub fn colliders_near(&self, min: Vector2<f32>, max: Vector2<f32>) -> impl Iterator<Item = &BoxCollider> {
+        self.grid.query(min, max).into_iter().filter_map(|id| self.collider(id))
+    }
+
+    pub fn record_cast(&self, cast: CastRecord) {
+        if self.record_casts {
+            self.casts.borrow_mut().push(cast);
+        }
+    }
+
+    //every cast recorded since the last call
+    pub fn take_casts(&self) -> Vec<CastRecord> {
+        self.casts.take()
+    }
+
+    //trigger events from the last call to update_triggers, in a stable order
+    pub fn trigger_events(&self) -> &[TriggerEvent] {
+        &self.events
+    }
+
+    //checks the player and every body against the triggers and works out what entered, stayed in or left each one
+    //call once per tick after everything has moved
+    pub fn update_triggers(&mut self, player: &BoxCollider) {
+        let mut overlapping = BTreeSet::new();
+        for mover in self.bodies.iter().map(|body| &body.collider).chain([player]) {
+            for id in self.trigger_grid.query(mover.min(), mover.max()) {
+                let Some(trigger) = self.collider(id) else {
+                    continue;
+                };
+                if trigger.interacts(mover) && trigger.overlaps(mover) {
+                    overlapping.insert((trigger.id, mover.id));
+                }
+            }
+        }
+
+        self.events.clear();
+        for &(trigger, other) in self.overlapping.union(&overlapping) {
+            let kind = match (self.overlapping.contains(&(trigger, other)), overlapping.contains(&(trigger, other))) {
+                (false, true) => TriggerEventKind::Enter,
+                (true, true) => TriggerEventKind::Stay,
+                _ => TriggerEventKind::Exit,
+            };
+            self.events.push(TriggerEvent { kind, trigger, other });
+        }
+        self.overlapping = overlapping;
+
+        for event in &self.events {
+            if event.kind == TriggerEventKind::Enter {
+                for kinematic in self.kinematics.iter_mut().filter(|kinematic| kinematic.activated_by == Some(event.trigger)) {
+                    kinematic.activate();
+                }
+            }
+        }
+    }
+
+    //something with pusher_mass moving at speed ran into a body from the side, share the momentum with it
+    pub fn push(&mut self, id: ColliderId, speed: f32, pusher_mass: f32) {
+        if let ColliderId::Body(i) = id {
+            let body = &mut self.bodies[i];
+            body.vel.x = (pusher_mass * speed + body.mass * body.vel.x) / (pusher_mass + body.mass);
+        }
+    }
+
+    //runs one physics tick, delta is the fixed tick length in milliseconds
+    pub fn step(&mut self, delta: f32, terrain: &dyn Terrain) {
+        //kinematic bodies go first so everything else reacts to where they are this tick
+        for kinematic in &mut self.kinematics {
+            kinematic.advance();
+            self.grid.update(&kinematic.collider);
+        }
+
+        for i in 0..self.bodies.len() {
+            let body = &mut self.bodies[i];
+            body.prev_pos = body.collider.pos;
+            body.vel.y -= GRAVITY * body.gravity_scale * delta;
+            body.grounded = false;
+            let vel = body.vel;
+
+            self.move_body(i, Vector2::new(0.0, vel.y), terrain);
+            self.move_body(i, Vector2::new(vel.x, 0.0), terrain);
+
+            let body = &mut self.bodies[i];
+            if body.grounded {
+                body.vel.x *= 1.0 - body.friction;
+            }
+            self.grid.update(&self.bodies[i].collider);
+        }
+    }
+
+    //moves body i by motion, stopping at the first terrain collider or body in the way
+    //slopes are treated as full boxes here, only the player walks along them
+    fn move_body(&mut self, i: usize, motion: Vector2<f32>, terrain: &dyn Terrain) {
+        let distance = motion.magnitude();
+        if distance == 0.0 {
+            return;
+        }
+
+        let collider = &self.bodies[i].collider;
+        let min = collider.min() + motion.map(|m| m.min(0.0));
+        let max = collider.max() + motion.map(|m| m.max(0.0));
+        let closest = terrain.colliders_near(min, max).iter()
+            .chain(self.colliders_near(min, max))
+            .filter(|other_coll| other_coll.id != collider.id && other_coll.blocks(collider, motion))
+            .filter_map(|other_coll| collider.sweep(motion, other_coll))
+            .min_by(|a, b| a.time.total_cmp(&b.time));
+
+        let Some(SweepHit { time, normal, other }) = closest else {
+            self.bodies[i].collider.pos += motion;
+            return;
+        };
+
+        let axis = if normal.x != 0.0 { 0 } else { 1 };
+        let body = &mut self.bodies[i];
+        body.collider.pos += motion * (time - SKIN / distance).max(0.0);
+        if normal.y > 0.0 {
+            body.grounded = true;
+        }
+
+        if let ColliderId::Body(j) = other {
+            //bodies hitting each other end up moving together, so heavy bodies shove light ones around
+            let (mass, speed) = (body.mass, body.vel[axis]);
+            let other_body = &mut self.bodies[j];
+            let shared = (mass * speed + other_body.mass * other_body.vel[axis]) / (mass + other_body.mass);
+            other_body.vel[axis] = shared;
+            self.bodies[i].vel[axis] = shared;
+        } else {
+            body.vel[axis] *= -body.restitution;
+            if body.vel[axis].abs() < REST_SPEED {
+                body.vel[axis] = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::Vector2;
+
+    use crate::physics::{boxCollider::{BoxCollider, ColliderId, LAYER_BODY}, trigger::TriggerEventKind};
+
+    use super::PhysicsWorld;
+
+    #[test]
+    fn test_trigger_enter_stay_exit() {
+        let mut world = PhysicsWorld::new(50.0);
+        let trigger = world.add_trigger(BoxCollider::new(Vector2::new(0.0, 0.0), Vector2::new(100.0, 100.0)));
+        let mut player = BoxCollider::new(Vector2::new(200.0, 0.0), Vector2::new(50.0, 50.0));
+        player.id = ColliderId::Player;
+
+        let kinds = |world: &PhysicsWorld| world.trigger_events().iter().map(|event| event.kind).collect::<Vec<_>>();
+
+        world.update_triggers(&player);
+        assert!(kinds(&world).is_empty());
+
+        player.pos.x = 40.0;
+        world.update_triggers(&player);
+        assert_eq!(kinds(&world), vec![TriggerEventKind::Enter]);
+        assert_eq!(world.trigger_events()[0].trigger, trigger);
+        assert_eq!(world.trigger_events()[0].other, ColliderId::Player);
+
+        world.update_triggers(&player);
+        assert_eq!(kinds(&world), vec![TriggerEventKind::Stay]);
+
+        player.pos.x = 200.0;
+        world.update_triggers(&player);
+        assert_eq!(kinds(&world), vec![TriggerEventKind::Exit]);
+
+        //a trigger that only reacts to bodies ignores the player
+        world.triggers[0].mask = LAYER_BODY;
+        player.pos.x = 0.0;
+        world.update_triggers(&player);
+        assert!(kinds(&world).is_empty());
+    }
+}
\ No newline at end of file
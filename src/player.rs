@@ -1,90 +1,640 @@
-use cgmath::{Vector2, Vector3};
-use wgpu::{Device, RenderPass};
-use winit::keyboard::KeyCode;
+use cgmath::{InnerSpace, Vector2};
 
-use crate::{physics::boxCollider::BoxCollider, shaders::ShaderManager, sprite::Sprite};
+use crate::{health::Health, input::ActionState, physics::{boxCollider::{BoxCollider, ColliderId, ColliderKind, Contact, SweepHit, CONTACT_TOLERANCE, LAYER_PLAYER}, terrain::Terrain, world::PhysicsWorld}, save::Abilities};
 
+use self::{config::PlayerConfig, state::{PlayerInput, PlayerState}};
 
+pub mod config;
+pub mod state;
+
+//distance kept between the player and whatever it hits so rounding errors don't leave it inside a collider
+const SKIN: f32 = 0.01;
+//how heavy the player is when pushing bodies around
+const PUSH_MASS: f32 = 1.0;
 
 pub struct Player {
     pub pos: Vector2<f32>,
+    //pos at the start of the last tick, rendering blends between this and pos
+    prev_pos: Vector2<f32>,
+    //world units per tick, with y going up like the rest of the physics module
     vel: Vector2<f32>,
-    sprite: Sprite,
     collider: BoxCollider,
     touching_ground: bool,
+    //everything the player was touching at the end of the last update
+    pub contacts: Vec<Contact>,
+    //one way platform the player is dropping through, ignored until the player is clear of it
+    dropping_through: Option<ColliderId>,
+    //a moving platform pushed the player into something it couldn't get out of this tick
+    pub crushed: bool,
+    pub config: PlayerConfig,
+    //time left to jump after leaving the ground
+    coyote_timer: f32,
+    //time left on a jump press that hasn't been used yet
+    jump_buffer: f32,
+    jump_held: bool,
+    pub abilities: Abilities,
+    //1 when facing +x, -1 when facing -x
+    pub facing: f32,
+    dash_cooldown: f32,
+    //only one dash is allowed each time the player leaves the ground
+    air_dash_used: bool,
+    state: PlayerState,
+    state_time: f32,
+    //how far the current state walks the player this tick
+    walk: f32,
+    //walking speed in world units per millisecond, eased towards the input by acceleration and deceleration
+    walk_speed: f32,
+    pub health: Health,
+    //how far the platform the player is standing on moved last tick, kept as velocity once the player leaves it
+    platform_delta: Vector2<f32>,
 }
 
 impl Player {
-    pub fn new(pos: Vector2<f32>, sprite: Sprite) -> Self {
-        let collider = BoxCollider::new(pos, Vector2::new(50.0, 50.0));
+    pub fn new(pos: Vector2<f32>) -> Self {
+        let config = PlayerConfig::default();
+        let health = Health::new(config.max_health);
+        let mut collider = BoxCollider::new(pos, config.collider_size.into());
+        collider.id = ColliderId::Player;
+        collider.layer = LAYER_PLAYER;
         let vel = Vector2::new(0.0, 0.0);
         let touching_ground = false;
 
         Self {
             pos,
-            sprite,
+            prev_pos: pos,
             collider,
             vel,
             touching_ground,
+            contacts: Vec::new(),
+            dropping_through: None,
+            crushed: false,
+            config,
+            coyote_timer: 0.0,
+            jump_buffer: 0.0,
+            jump_held: false,
+            abilities: Abilities::default(),
+            facing: 1.0,
+            dash_cooldown: 0.0,
+            air_dash_used: false,
+            state: PlayerState::Fall,
+            state_time: 0.0,
+            walk: 0.0,
+            walk_speed: 0.0,
+            health,
+            platform_delta: Vector2::new(0.0, 0.0),
+        }
+    }
+
+    //where to draw the player, alpha is how far we are between the last tick and the next one
+    pub fn render_pos(&self, alpha: f32) -> Vector2<f32> {
+        self.prev_pos + (self.pos - self.prev_pos) * alpha
+    }
+
+    pub fn collider(&self) -> &BoxCollider {
+        &self.collider
+    }
+
+    //swaps in a new config, a new collider size keeps the player's feet where they were
+    pub fn set_config(&mut self, config: PlayerConfig) {
+        let old_size = self.collider.size();
+        let new_size: Vector2<f32> = config.collider_size.into();
+        self.pos.y += (new_size.y - old_size.y) / 2.0;
+        self.prev_pos.y += (new_size.y - old_size.y) / 2.0;
+        self.collider.set_size(new_size);
+        self.collider.pos = self.pos;
+        self.health.max = config.max_health;
+        self.health.current = self.health.current.min(config.max_health);
+        self.config = config;
+    }
+
+    //hurts the player unless it is still invulnerable, away is the direction to knock it in, -1 or 1 along x
+    //returns whether the hit landed
+    pub fn take_hit(&mut self, damage: i32, away: f32) -> bool {
+        if self.state == PlayerState::Dead || !self.health.damage(damage, self.config.invulnerable_time) {
+            return false;
         }
+        if self.health.is_dead() {
+            self.kill();
+        } else {
+            self.hurt(Vector2::new(away * self.config.knockback[0], self.config.knockback[1]));
+        }
+        true
     }
 
-    pub fn render<'s: 'b + 'm, 'b,'m: 'b>(&'s mut self, render_pass: &mut RenderPass<'b>, shader_man: * mut ShaderManager) {
-        self.sprite.render(render_pass, shader_man);
+    //puts the player back at pos with full health
+    pub fn respawn(&mut self, pos: Vector2<f32>) {
+        self.pos = pos;
+        self.prev_pos = pos;
+        self.collider.pos = pos;
+        self.vel = Vector2::new(0.0, 0.0);
+        self.walk_speed = 0.0;
+        self.contacts.clear();
+        self.touching_ground = false;
+        self.dropping_through = None;
+        self.platform_delta = Vector2::new(0.0, 0.0);
+        self.health.reset();
+        self.set_state(PlayerState::Fall);
     }
 
-    pub fn handle_input(&mut self, keys_down: &Vec<KeyCode>, device: &Device, delta: f32, terrain: &Vec<BoxCollider>) {
-        let speed = 0.2;
-        let previous_y = self.pos.y;
-        let previous_x = self.pos.x;
-        let jump_force = -8.0;
+    //runs one physics tick, delta is the fixed tick length in milliseconds
+    pub fn handle_input(&mut self, actions: &ActionState, delta: f32, terrain: &dyn Terrain, world: &mut PhysicsWorld) {
+        self.prev_pos = self.pos;
+        self.ride_platforms(terrain, world);
 
-        let mut move_amount = Vector2::new(0.0, 0.0);
+        let input = PlayerInput::new(actions);
+        self.jump_held = input.jump;
+        self.update_timers(&input, delta);
 
-        if keys_down.contains(&KeyCode::KeyA) {
-            move_amount -= Vector2::new(1.0, 0.0) * speed * delta;
+        self.walk = 0.0;
+        self.state_time += delta;
+        if let Some(next) = self.update_state(&input, delta) {
+            self.set_state(next);
+        }
+        if self.crushed {
+            self.kill();
+        }
+
+        if self.state.has_gravity() {
+            let at_apex = self.jump_held && self.vel.y.abs() < self.config.apex_threshold;
+            let gravity = if at_apex { self.config.gravity * self.config.apex_gravity_scale } else { self.config.gravity };
+            let max_fall_speed = if self.state == PlayerState::WallSlide { self.config.wall_slide_speed } else { self.config.terminal_velocity };
+            self.vel.y = (self.vel.y - gravity * delta).max(-max_fall_speed);
+        }
+
+        let move_amount = Vector2::new(self.walk + self.vel.x, self.vel.y);
+        self.vel.x *= 1.0 - self.config.air_drag;
+
+        let was_grounded = self.touching_ground;
+
+        self.move_and_collide(Vector2::new(0.0, move_amount.y), terrain, world);
+        self.land_on_slopes(move_amount.y.abs() + CONTACT_TOLERANCE, terrain);
+
+        //on the ground the player can walk up anything as steep as 45 degrees
+        let max_step = move_amount.x.abs() + CONTACT_TOLERANCE;
+        let before_x = self.pos;
+        if let Some(hit) = self.move_and_collide(Vector2::new(move_amount.x, 0.0), terrain, world) {
+            self.vel.x = 0.0;
+            if let ColliderId::Body(_) = hit.other {
+                world.push(hit.other, move_amount.x, PUSH_MASS);
+            } else if was_grounded {
+                self.step_up(&hit, Vector2::new(move_amount.x, 0.0), max_step, terrain, world);
+            }
+        }
+        //the high side of a slope is a wall
+        if !self.land_on_slopes(max_step, terrain) {
+            self.pos = before_x;
+            self.collider.pos = self.pos;
+        }
+
+        //stay stuck to the ground when walking down slopes instead of falling off them every step
+        if was_grounded && self.vel.y <= 0.0 {
+            self.snap_to_ground(max_step, terrain, world);
+        }
+
+        self.resolve_contacts(terrain, world);
+        self.touch_hazards();
+    }
+
+    //hazards hurt the player and knock it back the way it came
+    fn touch_hazards(&mut self) {
+        let Some(hazard) = self.contacts.iter().find(|contact| contact.kind == ColliderKind::Hazard) else {
+            return;
+        };
+        let away = if hazard.normal.x != 0.0 { hazard.normal.x.signum() } else { -self.facing };
+        self.take_hit(1, away);
+    }
+
+    //counts down the jump and dash timers, coyote time keeps getting topped up while on the ground
+    fn update_timers(&mut self, input: &PlayerInput, delta: f32) {
+        if self.touching_ground {
+            self.coyote_timer = self.config.coyote_time;
+            self.air_dash_used = false;
+        } else {
+            self.coyote_timer -= delta;
+        }
+        if input.jump_pressed {
+            self.jump_buffer = self.config.buffer_time;
+        } else {
+            self.jump_buffer -= delta;
         }
-        if keys_down.contains(&KeyCode::KeyD) {
-            move_amount += Vector2::new(1.0, 0.0) * speed * delta;
+        self.dash_cooldown -= delta;
+        self.health.tick(delta);
+    }
+
+    //1 if the player is touching a wall on its +x side, -1 for the -x side
+    fn wall_direction(&self) -> Option<f32> {
+        self.contacts.iter()
+            .find(|contact| contact.normal.x != 0.0 && contact.normal.y == 0.0 && self.blocks_contact(contact))
+            .map(|contact| -contact.normal.x)
+    }
+
+    //moves the player by motion, stopping exactly at the first collider in the way
+    fn move_and_collide(&mut self, motion: Vector2<f32>, terrain: &dyn Terrain, world: &PhysicsWorld) -> Option<SweepHit> {
+        let distance = motion.magnitude();
+        if distance == 0.0 {
+            return None;
         }
-        if keys_down.contains(&KeyCode::KeyW) && self.touching_ground {
-            self.vel.y = jump_force;
+
+        self.collider.pos = self.pos;
+        //only check the colliders around the path the player takes
+        let min = self.collider.min() + motion.map(|m| m.min(0.0));
+        let max = self.collider.max() + motion.map(|m| m.max(0.0));
+        let mut closest: Option<SweepHit> = None;
+        for other_coll in terrain.colliders_near(min, max).iter().chain(world.colliders_near(min, max)).filter(|other_coll| self.blocks_motion(other_coll, motion)) {
+            if let Some(hit) = self.collider.sweep(motion, other_coll) {
+                if closest.as_ref().map_or(true, |c| hit.time < c.time) {
+                    closest = Some(hit);
+                }
+            }
         }
 
-        //gravity constant
-        self.vel.y += 0.01 * delta;
+        let time = match &closest {
+            Some(hit) => (hit.time - SKIN / distance).max(0.0),
+            None => 1.0,
+        };
+        self.pos += motion * time;
+        self.collider.pos = self.pos;
+
+        closest
+    }
 
-        move_amount.y -= self.vel.y;
+    //finds what the player is touching, pushes it out of anything it ended up inside and stops velocity going into the ground or ceiling
+    fn resolve_contacts(&mut self, terrain: &dyn Terrain, world: &PhysicsWorld) {
+        self.collider.pos = self.pos;
+        let tolerance = Vector2::new(CONTACT_TOLERANCE, CONTACT_TOLERANCE);
+        let (min, max) = (self.collider.min() - tolerance, self.collider.max() + tolerance);
+        self.contacts = terrain.colliders_near(min, max).iter().chain(world.colliders_near(min, max))
+            .filter(|other_coll| other_coll.slope.is_none() && !other_coll.is_trigger && other_coll.interacts(&self.collider))
+            .filter_map(|other_coll| self.collider.contact(other_coll))
+            .collect();
+        //box contacts don't make sense for slopes, so standing on one gets a contact with the slope's normal instead
+        if let Some((_, slope_coll)) = self.slope_surface(terrain, CONTACT_TOLERANCE, CONTACT_TOLERANCE) {
+            let [at_min, at_max] = slope_coll.slope.unwrap();
+            let rise = (at_max - at_min) * slope_coll.size().y / slope_coll.size().x;
+            self.contacts.push(Contact { depth: 0.0, normal: Vector2::new(-rise, 1.0).normalize(), other: slope_coll.id, kind: slope_coll.kind });
+        }
 
-        let mut collided_y = false;
-        let mut collided_x = false;
+        if let Some(id) = self.dropping_through {
+            if !self.contacts.iter().any(|contact| contact.other == id) {
+                self.dropping_through = None;
+            }
+        }
 
+        let blocking: Vec<(Vector2<f32>, f32)> = self.contacts.iter()
+            .filter(|contact| self.blocks_contact(contact))
+            .map(|contact| (contact.normal, contact.depth))
+            .collect();
 
         self.touching_ground = false;
-        for other_coll in terrain {
-            self.collider.pos = Vector2::new(self.pos.x, self.pos.y + move_amount.y);
-            if self.collider.CheckCollision(&other_coll)
-            {
-                self.vel.y = 0.0;
+        for (normal, depth) in blocking {
+            if depth > 0.0 {
+                self.pos += normal * (depth + SKIN);
+            }
+            if normal.y > 0.0 {
                 self.touching_ground = true;
-                collided_y = true;
+                if self.vel.y < 0.0 {
+                    self.vel.y = 0.0;
+                }
+            } else if normal.y < 0.0 && self.vel.y > 0.0 {
+                self.vel.y = 0.0;
             }
-            self.collider.pos = Vector2::new(self.pos.x + move_amount.x, self.pos.y);
-            if self.collider.CheckCollision(&other_coll)
-            {
-                collided_x = true;
+        }
+        self.collider.pos = self.pos;
+    }
+
+    //whether other stops the player moving by motion, checked from the position before the move
+    fn blocks_motion(&self, other: &BoxCollider, motion: Vector2<f32>) -> bool {
+        //slopes are walked on by land_on_slopes, the sweep only stops the player jumping into the underside
+        if other.slope.is_some() {
+            return other.kind.blocks() && motion.y > 0.0;
+        }
+        self.dropping_through != Some(other.id) && other.blocks(&self.collider, motion)
+    }
+
+    //whether a contact should hold the player up or push them out
+    fn blocks_contact(&self, contact: &Contact) -> bool {
+        match contact.kind {
+            ColliderKind::Solid => true,
+            //only standing on top counts, jumping up through the platform or being partway inside it doesn't
+            ColliderKind::OneWay => contact.normal.y > 0.0
+                && contact.depth == 0.0
+                && self.vel.y <= 0.0
+                && self.dropping_through != Some(contact.other),
+            ColliderKind::Hazard => false,
+        }
+    }
+
+    //highest slope surface under the player's feet that is between below under and above over the bottom of the player
+    fn slope_surface(&self, terrain: &dyn Terrain, below: f32, above: f32) -> Option<(f32, BoxCollider)> {
+        let bottom = self.collider.min().y;
+        let min = Vector2::new(self.collider.min().x, bottom - below);
+        let max = Vector2::new(self.collider.max().x, bottom + above);
+        terrain.colliders_near(min, max).into_iter()
+            .filter(|other_coll| other_coll.slope.is_some() && other_coll.kind.blocks() && other_coll.min().x < max.x && other_coll.max().x > min.x)
+            .map(|other_coll| (other_coll.highest_surface(min.x, max.x), other_coll))
+            .filter(|(surface, _)| *surface >= min.y && *surface <= max.y)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    //lifts the player onto any slope it sank into by at most max_step
+    //returns false if a slope is sticking further than that into the player, which means it walked into the high side
+    fn land_on_slopes(&mut self, max_step: f32, terrain: &dyn Terrain) -> bool {
+        let height = self.collider.size().y;
+        match self.slope_surface(terrain, 0.0, height) {
+            Some((surface, _)) if surface - self.collider.min().y > max_step => false,
+            Some((surface, _)) => {
+                self.pos.y += surface - self.collider.min().y + SKIN;
+                self.collider.pos = self.pos;
+                true
+            },
+            None => true,
+        }
+    }
+
+    //walks up onto a box the player ran into if its top is at most max_step above the player's feet, then carries on moving
+    fn step_up(&mut self, hit: &SweepHit, motion: Vector2<f32>, max_step: f32, terrain: &dyn Terrain, world: &PhysicsWorld) {
+        let Some(other_coll) = terrain.collider(hit.other) else {
+            return;
+        };
+        let rise = other_coll.max().y - self.collider.min().y;
+        if hit.normal.x != 0.0 && rise > 0.0 && rise <= max_step {
+            self.pos.y += rise + SKIN;
+            self.move_and_collide(motion * (1.0 - hit.time), terrain, world);
+        }
+    }
+
+    //moves the player down onto the ground if it is at most distance below, otherwise leaves it where it is
+    fn snap_to_ground(&mut self, distance: f32, terrain: &dyn Terrain, world: &PhysicsWorld) {
+        let before = self.pos;
+        let hit_ground = self.move_and_collide(Vector2::new(0.0, -distance), terrain, world).is_some();
+        if self.slope_surface(terrain, 0.0, distance + SKIN).is_some() {
+            self.land_on_slopes(distance + SKIN, terrain);
+        } else if !hit_ground {
+            self.pos = before;
+            self.collider.pos = self.pos;
+        }
+    }
+
+    //moving platforms have already moved this tick, so carry the player along with the one it is standing on
+    //and shove it out of any that moved into it
+    fn ride_platforms(&mut self, terrain: &dyn Terrain, world: &PhysicsWorld) {
+        let riding = self.contacts.iter()
+            .filter(|contact| contact.normal.y > 0.0 && self.blocks_contact(contact))
+            .find_map(|contact| world.kinematic(contact.other))
+            .map(|platform| platform.delta);
+        match riding {
+            Some(delta) => {
+                self.move_and_collide(Vector2::new(0.0, delta.y), terrain, world);
+                self.move_and_collide(Vector2::new(delta.x, 0.0), terrain, world);
+                self.platform_delta = delta;
+            },
+            //jumping or walking off keeps the platform's speed, air drag takes it away over time
+            None => {
+                self.vel += self.platform_delta;
+                self.platform_delta = Vector2::new(0.0, 0.0);
+            },
+        }
+
+        self.crushed = false;
+        self.collider.pos = self.pos;
+        for platform in &world.kinematics {
+            if platform.collider.kind != ColliderKind::Solid || !platform.collider.interacts(&self.collider) {
+                continue;
+            }
+            let Some(contact) = self.collider.contact(&platform.collider) else {
+                continue;
+            };
+            if contact.depth > 0.0 {
+                //the sweep ignores the platform since the player starts inside it, so this only stops at terrain
+                self.move_and_collide(contact.normal * (contact.depth + SKIN), terrain, world);
+                if self.collider.overlaps(&platform.collider) {
+                    self.crushed = true;
+                }
             }
         }
+    }
+}
 
-        if (!collided_y)
-        {
-            self.pos.y += move_amount.y;
+#[cfg(test)]
+mod test {
+    use cgmath::Vector2;
+
+    use crate::{input::{Action, ActionState, ActionValues}, physics::{boxCollider::{BoxCollider, ColliderId}, kinematic::{KinematicBody, PathMode}, terrain::Terrain, world::PhysicsWorld}, simulation::TICK_MS};
+
+    use super::{config::PlayerConfig, state::PlayerState, Player};
+
+    struct NoTerrain;
+
+    impl Terrain for NoTerrain {
+        fn colliders_near(&self, _min: Vector2<f32>, _max: Vector2<f32>) -> Vec<BoxCollider> {
+            vec![]
         }
-        if (!collided_x)
-        {
-            self.pos.x += move_amount.x;
+
+        fn collider(&self, _id: ColliderId) -> Option<BoxCollider> {
+            None
         }
+    }
 
-        self.sprite.set_position(Vector3::new(0.0, self.pos.y, self.pos.x), device);
+    //a few fixed boxes, numbered as tiles
+    struct Boxes(Vec<BoxCollider>);
+
+    impl Terrain for Boxes {
+        fn colliders_near(&self, _min: Vector2<f32>, _max: Vector2<f32>) -> Vec<BoxCollider> {
+            self.0.clone()
+        }
+
+        fn collider(&self, id: ColliderId) -> Option<BoxCollider> {
+            self.0.iter().find(|coll| coll.id == id).cloned()
+        }
+    }
+
+    //a player above a floor that runs from x -400 to 0 with its top at y 0
+    struct Ledge {
+        player: Player,
+        world: PhysicsWorld,
+        actions: ActionState,
+        terrain: Boxes,
     }
-}
\ No newline at end of file
+
+    impl Ledge {
+        fn new(player_pos: Vector2<f32>) -> Self {
+            let mut floor = BoxCollider::new(Vector2::new(-200.0, -10.0), Vector2::new(400.0, 20.0));
+            floor.id = ColliderId::Tile(0);
+            Self { player: Player::new(player_pos), world: PhysicsWorld::new(50.0), actions: ActionState::default(), terrain: Boxes(vec![floor]) }
+        }
+
+        fn step(&mut self, held: &[Action]) {
+            self.actions.advance(held.iter().map(|action| (*action, 1.0)).collect());
+            self.world.step(TICK_MS, &self.terrain);
+            self.player.handle_input(&self.actions, TICK_MS, &self.terrain, &mut self.world);
+        }
+
+        //steps until the player is in state, or gives up after max_ticks, and returns how many ticks it took
+        fn step_until(&mut self, held: &[Action], state: PlayerState, max_ticks: u32) -> Option<u32> {
+            (1..=max_ticks).find(|_| {
+                self.step(held);
+                self.player.state() == state
+            })
+        }
+
+        //jumps from standing, holding jump for hold ticks, and returns how high the player's feet got
+        fn jump_height(&mut self, hold: u32) -> f32 {
+            let ground = self.player.collider().min().y;
+            let mut highest = ground;
+            for tick in 0..120 {
+                self.step(if tick < hold { &[Action::Jump] } else { &[] });
+                highest = highest.max(self.player.collider().min().y);
+            }
+            highest - ground
+        }
+    }
+
+    #[test]
+    fn test_ride_and_jump_off_platform() {
+        let mut world = PhysicsWorld::new(50.0);
+        let platform = world.add_kinematic(KinematicBody::new(vec![Vector2::new(0.0, 0.0), Vector2::new(2000.0, 0.0)], Vector2::new(200.0, 20.0), PathMode::Linear, 2.0));
+        let mut player = Player::new(Vector2::new(0.0, 40.0));
+        let mut actions = ActionState::default();
+        let mut step = |player: &mut Player, world: &mut PhysicsWorld, values: ActionValues| {
+            actions.advance(values);
+            world.step(TICK_MS, &NoTerrain);
+            player.handle_input(&actions, TICK_MS, &NoTerrain, world);
+        };
+
+        let offset = |player: &Player, world: &PhysicsWorld| player.pos.x - world.kinematic(platform).unwrap().collider.pos.x;
+        //the platform moves off a little while the player is still landing on it
+        for _ in 0..15 {
+            step(&mut player, &mut world, ActionValues::new());
+        }
+        let landed = offset(&player, &world);
+        for _ in 0..30 {
+            step(&mut player, &mut world, ActionValues::new());
+        }
+        //then it is carried along without sliding
+        assert!((offset(&player, &world) - landed).abs() < 0.001, "player slid from {landed} to {}", offset(&player, &world));
+        assert_eq!(player.state(), PlayerState::Idle);
+
+        step(&mut player, &mut world, ActionValues::from([(Action::Jump, 1.0)]));
+        let jump_x = player.pos.x;
+        for _ in 0..10 {
+            step(&mut player, &mut world, ActionValues::from([(Action::Jump, 1.0)]));
+        }
+        //the platform's speed carries over into the jump instead of stopping dead
+        assert!(player.pos.x - jump_x > 10.0, "only moved {} after jumping", player.pos.x - jump_x);
+        assert!(player.vel.x > 0.0 && player.vel.x < 2.0);
+    }
+
+    #[test]
+    fn test_rising_platform_lifts_and_crushes() {
+        //a platform rising from under the player towards a ceiling with its bottom at y 190
+        let mut ceiling = BoxCollider::new(Vector2::new(0.0, 200.0), Vector2::new(400.0, 20.0));
+        ceiling.id = ColliderId::Tile(0);
+        let terrain = Boxes(vec![ceiling.clone()]);
+        let mut world = PhysicsWorld::new(50.0);
+        let platform = world.add_kinematic(KinematicBody::new(vec![Vector2::new(0.0, 0.0), Vector2::new(0.0, 400.0)], Vector2::new(200.0, 20.0), PathMode::Linear, 1.0));
+        let mut player = Player::new(Vector2::new(0.0, 40.0));
+        let actions = ActionState::default();
+        let mut step = |player: &mut Player, world: &mut PhysicsWorld| {
+            world.step(TICK_MS, &terrain);
+            player.handle_input(&actions, TICK_MS, &terrain, world);
+        };
+
+        for _ in 0..15 {
+            step(&mut player, &mut world);
+        }
+        let height = |player: &Player, world: &PhysicsWorld| player.collider().min().y - world.kinematic(platform).unwrap().collider.max().y;
+        let landed = height(&player, &world);
+        assert!(landed.abs() < 0.1, "player is {landed} above the platform");
+        //it is lifted along with the platform, standing the whole way
+        for _ in 0..60 {
+            step(&mut player, &mut world);
+            assert!((height(&player, &world) - landed).abs() < 0.001, "player came off the platform by {}", height(&player, &world) - landed);
+            assert_eq!(player.state(), PlayerState::Idle);
+        }
+
+        //until there is no room left under the ceiling
+        let mut ticks = 0;
+        while player.state() != PlayerState::Dead && ticks < 200 {
+            step(&mut player, &mut world);
+            ticks += 1;
+        }
+        assert_eq!(player.state(), PlayerState::Dead);
+        assert!(player.crushed);
+        let gap = ceiling.min().y - world.kinematic(platform).unwrap().collider.max().y;
+        assert!(gap <= player.collider().size().y, "crushed with {gap} of room");
+    }
+
+    #[test]
+    fn test_coyote_time() {
+        //100ms of coyote time is 6 ticks
+        for (wait, jumps) in [(3, true), (10, false)] {
+            let mut ledge = Ledge::new(Vector2::new(-100.0, 30.0));
+            ledge.step_until(&[], PlayerState::Idle, 30).unwrap();
+            ledge.step_until(&[Action::MoveRight], PlayerState::Fall, 120).unwrap();
+            for _ in 0..wait {
+                ledge.step(&[]);
+            }
+            ledge.step(&[Action::Jump]);
+            assert_eq!(ledge.player.state() == PlayerState::Jump, jumps, "jumping {wait} ticks after walking off");
+        }
+    }
+
+    #[test]
+    fn test_jump_buffer() {
+        //how long a fall onto the floor takes
+        let mut ledge = Ledge::new(Vector2::new(-200.0, 150.0));
+        let landing = ledge.step_until(&[], PlayerState::Land, 120).unwrap();
+
+        //120ms of buffer is 7 ticks, a press 3 ticks early jumps as soon as the player lands and one 15 ticks early is forgotten
+        for (early, jumps) in [(3, true), (15, false)] {
+            let mut ledge = Ledge::new(Vector2::new(-200.0, 150.0));
+            for _ in 0..landing - early {
+                ledge.step(&[]);
+            }
+            ledge.step(&[Action::Jump]);
+            let mut states = vec![];
+            for _ in 0..early + 2 {
+                ledge.step(&[]);
+                states.push(ledge.player.state());
+            }
+            assert_eq!(states.contains(&PlayerState::Jump), jumps, "pressed jump {early} ticks before landing, went through {states:?}");
+        }
+    }
+
+    #[test]
+    fn test_variable_jump_height() {
+        let mut ledge = Ledge::new(Vector2::new(-200.0, 30.0));
+        ledge.step_until(&[], PlayerState::Idle, 30).unwrap();
+        let full = ledge.jump_height(120);
+        ledge.step_until(&[], PlayerState::Idle, 120).unwrap();
+        let short = ledge.jump_height(3);
+        //letting go early cuts the upward speed by jump_cut, so the hop is much lower
+        assert!(short < full * 0.5, "short hop went {short} high, a full jump {full}");
+        assert!(short > 0.0);
+    }
+
+    #[test]
+    fn test_apex_hang_and_fall_cap() {
+        //holding jump makes gravity weaker around the top of the jump, so a held jump stays up longer than without the hang
+        let air_time = |config: PlayerConfig| {
+            let mut ledge = Ledge::new(Vector2::new(-200.0, 30.0));
+            ledge.player.set_config(config);
+            ledge.step_until(&[], PlayerState::Idle, 30).unwrap();
+            ledge.step(&[Action::Jump]);
+            ledge.step_until(&[Action::Jump], PlayerState::Land, 300).unwrap()
+        };
+        let hang = air_time(PlayerConfig::default());
+        let no_hang = air_time(PlayerConfig { apex_gravity_scale: 1.0, ..Default::default() });
+        assert!(hang > no_hang, "{hang} ticks in the air with the hang and {no_hang} without");
+
+        //falling with nothing below speeds up to terminal velocity and no further
+        let mut player = Player::new(Vector2::new(0.0, 0.0));
+        let actions = ActionState::default();
+        let mut world = PhysicsWorld::new(50.0);
+        for _ in 0..300 {
+            player.handle_input(&actions, TICK_MS, &NoTerrain, &mut world);
+            assert!(player.vel.y >= -player.config.terminal_velocity);
+        }
+        assert_eq!(player.vel.y, -player.config.terminal_velocity);
+    }
+}
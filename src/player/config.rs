@@ -0,0 +1,129 @@
+use std::{fs, time::SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+pub const PLAYER_CONFIG_PATH: &str = "src/res/player.json";
+
+//everything about how the player moves, times are in milliseconds and speeds are in world units per tick like vel
+//unless they say otherwise, anything missing from the file keeps its default
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct PlayerConfig {
+    //top walking speed in world units per millisecond
+    pub walk_speed: f32,
+    //how fast walking speeds up and slows down, in world units per millisecond every millisecond
+    pub acceleration: f32,
+    pub deceleration: f32,
+    //acceleration and deceleration are multiplied by this in the air
+    pub air_control: f32,
+    pub gravity: f32,
+    //fastest the player can fall
+    pub terminal_velocity: f32,
+    //upwards speed a jump starts with
+    pub jump_force: f32,
+    //how long after walking off a ledge the player can still jump
+    pub coyote_time: f32,
+    //how long a jump press is remembered before landing
+    pub buffer_time: f32,
+    //upwards speed is multiplied by this when jump is let go early, for short hops
+    pub jump_cut: f32,
+    //below this vertical speed with jump held the player is at the top of the jump
+    pub apex_threshold: f32,
+    //gravity is multiplied by this at the top of the jump so it hangs there a little
+    pub apex_gravity_scale: f32,
+    //fastest the player falls while sliding down a wall
+    pub wall_slide_speed: f32,
+    //sideways speed a wall jump starts with, away from the wall
+    pub wall_jump_push: f32,
+    //fraction of the sideways speed from wall jumps lost every tick
+    pub air_drag: f32,
+    //world units per millisecond
+    pub dash_speed: f32,
+    pub dash_time: f32,
+    //time after a dash starts before the next one can
+    pub dash_cooldown: f32,
+    pub collider_size: [f32; 2],
+    pub max_health: i32,
+    //how long the player can't be hurt again after a hit
+    pub invulnerable_time: f32,
+    //speed the player is knocked away from whatever hurt it, sideways and upwards
+    pub knockback: [f32; 2],
+    //how long the player stays dead before respawning
+    pub respawn_delay: f32,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            walk_speed: 0.2,
+            acceleration: 0.01,
+            deceleration: 0.015,
+            air_control: 0.6,
+            gravity: 0.01,
+            terminal_velocity: 12.0,
+            jump_force: 8.0,
+            coyote_time: 100.0,
+            buffer_time: 120.0,
+            jump_cut: 0.5,
+            apex_threshold: 1.0,
+            apex_gravity_scale: 0.5,
+            wall_slide_speed: 2.0,
+            wall_jump_push: 6.0,
+            air_drag: 0.08,
+            dash_speed: 1.0,
+            dash_time: 150.0,
+            dash_cooldown: 500.0,
+            collider_size: [50.0, 50.0],
+            max_health: 3,
+            invulnerable_time: 1000.0,
+            knockback: [5.0, 4.0],
+            respawn_delay: 1000.0,
+        }
+    }
+}
+
+//reloads the config whenever the file's modified time changes, so movement can be tuned while the game is running
+pub struct ConfigWatcher {
+    path: String,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+        }
+    }
+
+    //returns the new config if the file changed since the last poll
+    //a file that doesn't parse is reported and skipped, the player keeps its current config until it is fixed
+    pub fn poll(&mut self) -> Option<PlayerConfig> {
+        let modified = fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok()?;
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+        let contents = fs::read_to_string(&self.path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(config) => Some(config),
+            Err(err) => {
+                println!("couldn't load {}: {err}", self.path);
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PlayerConfig;
+
+    #[test]
+    fn test_partial_config() {
+        let config: PlayerConfig = serde_json::from_str(r#"{"jump_force": 10.0, "collider_size": [40.0, 60.0]}"#).unwrap();
+        assert_eq!(config.jump_force, 10.0);
+        assert_eq!(config.collider_size, [40.0, 60.0]);
+        assert_eq!(config.gravity, PlayerConfig::default().gravity);
+    }
+}
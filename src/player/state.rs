@@ -0,0 +1,271 @@
+use cgmath::Vector2;
+use crate::{input::{Action, ActionState}, physics::boxCollider::{ColliderKind, Contact}};
+
+use super::Player;
+
+//how long landing lasts before the player is back to standing or running, in milliseconds
+const LAND_TIME: f32 = 80.0;
+//how long the player loses control after getting hurt, in milliseconds
+const HURT_TIME: f32 = 400.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayerState {
+    Idle,
+    Run,
+    Jump,
+    Fall,
+    Land,
+    WallSlide,
+    Dash,
+    Hurt,
+    Dead,
+}
+
+impl PlayerState {
+    //dashes go in a straight line
+    pub fn has_gravity(&self) -> bool {
+        *self != PlayerState::Dash
+    }
+
+    //name of the animation clip the player's sprite plays in this state
+    pub fn clip(&self) -> &'static str {
+        match self {
+            PlayerState::Idle => "idle",
+            PlayerState::Run => "run",
+            PlayerState::Jump => "jump",
+            PlayerState::Fall => "fall",
+            PlayerState::Land => "land",
+            PlayerState::WallSlide => "wall_slide",
+            PlayerState::Dash => "dash",
+            PlayerState::Hurt => "hurt",
+            PlayerState::Dead => "dead",
+        }
+    }
+}
+
+//the actions the player cares about this tick
+pub struct PlayerInput {
+    //-1 to 1
+    pub x: f32,
+    pub jump: bool,
+    pub jump_pressed: bool,
+    pub down: bool,
+    pub dash_pressed: bool,
+}
+
+impl PlayerInput {
+    pub fn new(actions: &ActionState) -> Self {
+        Self {
+            x: actions.axis(Action::MoveLeft, Action::MoveRight),
+            jump: actions.pressed(Action::Jump),
+            jump_pressed: actions.just_pressed(Action::Jump),
+            down: actions.pressed(Action::MoveDown),
+            dash_pressed: actions.just_pressed(Action::Dash),
+        }
+    }
+}
+
+impl Player {
+    pub fn state(&self) -> PlayerState {
+        self.state
+    }
+
+    //milliseconds since the current state was entered
+    pub fn state_time(&self) -> f32 {
+        self.state_time
+    }
+
+    //knocks the player back and takes away control for a moment
+    pub fn hurt(&mut self, knockback: Vector2<f32>) {
+        if self.state == PlayerState::Dead {
+            return;
+        }
+        self.vel = knockback;
+        self.set_state(PlayerState::Hurt);
+    }
+
+    pub fn kill(&mut self) {
+        if self.state != PlayerState::Dead {
+            self.set_state(PlayerState::Dead);
+        }
+    }
+
+    //leaves the current state and enters next, even if it is the same state
+    pub(super) fn set_state(&mut self, next: PlayerState) {
+        self.exit_state(self.state);
+        self.state = next;
+        self.state_time = 0.0;
+        self.enter_state(next);
+    }
+
+    fn enter_state(&mut self, state: PlayerState) {
+        match state {
+            PlayerState::Jump => {
+                self.vel.y = self.config.jump_force;
+                self.jump_buffer = 0.0;
+                self.coyote_timer = 0.0;
+            },
+            PlayerState::Dash => {
+                self.dash_cooldown = self.config.dash_cooldown;
+                self.air_dash_used = !self.touching_ground;
+                self.vel = Vector2::new(0.0, 0.0);
+            },
+            PlayerState::Hurt => {
+                self.walk_speed = 0.0;
+            },
+            PlayerState::Dead => {
+                self.vel = Vector2::new(0.0, 0.0);
+                self.walk_speed = 0.0;
+            },
+            _ => {},
+        }
+    }
+
+    fn exit_state(&mut self, state: PlayerState) {
+        //the dash's speed doesn't carry over into whatever comes next
+        if state == PlayerState::Dash {
+            self.vel.x = 0.0;
+        }
+    }
+
+    //does the state's work for this tick and returns the state to change to, if any
+    //how far the player walks this tick is left in self.walk
+    pub(super) fn update_state(&mut self, input: &PlayerInput, delta: f32) -> Option<PlayerState> {
+        let wall = self.wall_direction();
+        match self.state {
+            PlayerState::Idle | PlayerState::Run | PlayerState::Land => {
+                self.steer(input, delta);
+                self.drop_through(input);
+                if self.can_dash(input) {
+                    return Some(PlayerState::Dash);
+                }
+                if self.can_jump() {
+                    return Some(PlayerState::Jump);
+                }
+                if !self.touching_ground {
+                    return Some(PlayerState::Fall);
+                }
+                let next = if self.state == PlayerState::Land && self.state_time < LAND_TIME {
+                    PlayerState::Land
+                } else if input.x != 0.0 {
+                    PlayerState::Run
+                } else {
+                    PlayerState::Idle
+                };
+                (next != self.state).then_some(next)
+            },
+            PlayerState::Jump | PlayerState::Fall => {
+                self.steer(input, delta);
+                if self.can_dash(input) {
+                    return Some(PlayerState::Dash);
+                }
+                //coyote time lets the player jump for a moment after running off a ledge
+                if self.can_jump() {
+                    return Some(PlayerState::Jump);
+                }
+                if let Some(wall) = self.can_wall_jump(wall) {
+                    self.wall_jump(wall);
+                    return Some(PlayerState::Jump);
+                }
+                if self.touching_ground && self.vel.y <= 0.0 {
+                    return Some(PlayerState::Land);
+                }
+                if self.vel.y < 0.0 && self.can_wall_slide(input, wall) {
+                    return Some(PlayerState::WallSlide);
+                }
+                if self.state == PlayerState::Jump {
+                    //letting go of jump early cuts it short
+                    if !input.jump && self.vel.y > 0.0 {
+                        self.vel.y *= self.config.jump_cut;
+                        return Some(PlayerState::Fall);
+                    }
+                    if self.vel.y <= 0.0 {
+                        return Some(PlayerState::Fall);
+                    }
+                }
+                None
+            },
+            PlayerState::WallSlide => {
+                self.steer(input, delta);
+                if let Some(wall) = self.can_wall_jump(wall) {
+                    self.wall_jump(wall);
+                    return Some(PlayerState::Jump);
+                }
+                if self.can_dash(input) {
+                    return Some(PlayerState::Dash);
+                }
+                if self.touching_ground {
+                    return Some(PlayerState::Land);
+                }
+                if !self.can_wall_slide(input, wall) {
+                    return Some(PlayerState::Fall);
+                }
+                None
+            },
+            PlayerState::Dash => {
+                self.walk = self.facing * self.config.dash_speed * delta;
+                self.vel.y = 0.0;
+                if self.state_time < self.config.dash_time {
+                    return None;
+                }
+                Some(if self.touching_ground { PlayerState::Idle } else { PlayerState::Fall })
+            },
+            PlayerState::Hurt => {
+                if self.state_time < HURT_TIME {
+                    return None;
+                }
+                Some(if self.touching_ground { PlayerState::Idle } else { PlayerState::Fall })
+            },
+            PlayerState::Dead => None,
+        }
+    }
+
+    //walking and air control, speeding up towards the input direction and slowing down without one
+    fn steer(&mut self, input: &PlayerInput, delta: f32) {
+        let target = input.x * self.config.walk_speed;
+        let mut rate = if input.x != 0.0 { self.config.acceleration } else { self.config.deceleration };
+        if !self.touching_ground {
+            rate *= self.config.air_control;
+        }
+        let change = (target - self.walk_speed).clamp(-rate * delta, rate * delta);
+        self.walk_speed += change;
+        self.walk = self.walk_speed * delta;
+        if input.x != 0.0 {
+            self.facing = input.x.signum();
+        }
+    }
+
+    //pressing down on a one way platform drops through it, unless solid ground is also underneath
+    fn drop_through(&mut self, input: &PlayerInput) {
+        if !input.down || !self.touching_ground {
+            return;
+        }
+        let ground: Vec<&Contact> = self.contacts.iter().filter(|contact| contact.normal.y > 0.0 && contact.kind.blocks()).collect();
+        if ground.iter().all(|contact| contact.kind == ColliderKind::OneWay) {
+            self.dropping_through = ground.first().map(|contact| contact.other);
+        }
+    }
+
+    fn can_jump(&self) -> bool {
+        self.jump_buffer > 0.0 && self.coyote_timer > 0.0
+    }
+
+    fn can_dash(&self, input: &PlayerInput) -> bool {
+        self.abilities.dash && input.dash_pressed && self.dash_cooldown <= 0.0 && !self.air_dash_used
+    }
+
+    //the side the wall is on if a buffered jump can push off it
+    fn can_wall_jump(&self, wall: Option<f32>) -> Option<f32> {
+        wall.filter(|_| self.abilities.wall_jump && self.jump_buffer > 0.0 && !self.touching_ground)
+    }
+
+    //sliding down a wall needs the player to be pushing into it
+    fn can_wall_slide(&self, input: &PlayerInput, wall: Option<f32>) -> bool {
+        self.abilities.wall_slide && !self.touching_ground && input.x != 0.0 && wall == Some(input.x.signum())
+    }
+
+    fn wall_jump(&mut self, wall: f32) {
+        self.vel.x = -wall * self.config.wall_jump_push;
+        self.facing = -wall;
+    }
+}
@@ -0,0 +1,29 @@
+use cgmath::Vector3;
+use wgpu::{Device, RenderPass};
+
+use crate::{physics::{boxCollider::ColliderId, world::PhysicsWorld}, shaders::ShaderManager, sprite::Sprite};
+
+//a sprite that follows a dynamic or kinematic body, like a crate or a moving platform
+pub struct Prop {
+    pub body: ColliderId,
+    sprite: Sprite,
+}
+
+impl Prop {
+    pub fn new(body: ColliderId, sprite: Sprite) -> Self {
+        Self {
+            body,
+            sprite,
+        }
+    }
+
+    pub fn update_sprite(&mut self, world: &PhysicsWorld, alpha: f32, device: &Device) {
+        if let Some(render_pos) = world.render_pos(self.body, alpha) {
+            self.sprite.set_position(Vector3::new(0.0, render_pos.y, render_pos.x), device);
+        }
+    }
+
+    pub fn render<'s: 'b, 'b>(&'s mut self, render_pass: &mut RenderPass<'b>, shader_man: * mut ShaderManager) {
+        self.sprite.render(render_pass, shader_man);
+    }
+}
@@ -0,0 +1,158 @@
+use std::{fs::{self, File}, io::{self, Write}, time::{SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{input::{Action, ActionValues, InputMap}, player::config::PlayerConfig, save::Abilities};
+
+//written at the top of every recording, everything needed to start the run the same way again
+//the abilities and config are stored so a different save or player.json can't change how the run plays back
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ReplayHeader {
+    pub level: String,
+    //nothing in the game is random yet, this is kept so recordings stay valid once something is
+    pub seed: u64,
+    #[serde(default)]
+    pub abilities: Abilities,
+    #[serde(default)]
+    pub config: PlayerConfig,
+}
+
+//writes the header and then one line of action values per tick
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    pub fn new(path: &str, header: &ReplayHeader) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", serde_json::to_string(header)?)?;
+        Ok(Self { file })
+    }
+
+    pub fn record(&mut self, values: &ActionValues) -> io::Result<()> {
+        writeln!(self.file, "{}", serde_json::to_string(values)?)
+    }
+}
+
+pub struct Playback {
+    pub header: ReplayHeader,
+    ticks: Vec<ActionValues>,
+    next: usize,
+}
+
+impl Playback {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        let header = serde_json::from_str(lines.next().unwrap_or_default())?;
+        let ticks = lines.map(serde_json::from_str).collect::<Result<_, _>>()?;
+        Ok(Self { header, ticks, next: 0 })
+    }
+
+    pub fn next_tick(&mut self) -> Option<ActionValues> {
+        let values = self.ticks.get(self.next).cloned();
+        self.next += 1;
+        values
+    }
+}
+
+//where each tick's actions come from
+pub enum ReplayMode {
+    Live,
+    Record(Recorder),
+    Playback(Playback),
+}
+
+impl ReplayMode {
+    //reads --record <file>, --playback <file> and --seed <number> from the command line
+    //returns the mode along with what the run should start with, which comes from the recording during playback
+    pub fn from_args(args: impl Iterator<Item = String>, default_level: &str, abilities: Abilities, config: PlayerConfig) -> (Self, ReplayHeader) {
+        let args: Vec<String> = args.collect();
+        let value = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1));
+        let seed = value("--seed").and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64);
+        let header = ReplayHeader { level: default_level.into(), seed, abilities, config };
+
+        if let Some(path) = value("--playback") {
+            match Playback::load(path) {
+                Ok(playback) => {
+                    let header = playback.header.clone();
+                    return (ReplayMode::Playback(playback), header);
+                },
+                Err(err) => println!("couldn't load recording {path}: {err}"),
+            }
+        } else if let Some(path) = value("--record") {
+            match Recorder::new(path, &header) {
+                Ok(recorder) => return (ReplayMode::Record(recorder), header),
+                Err(err) => println!("couldn't start recording {path}: {err}"),
+            }
+        }
+        (ReplayMode::Live, header)
+    }
+
+    //the actions for the next tick
+    //during playback the live input is only used for things that don't change the simulation, like the debug overlay
+    pub fn tick_input(&mut self, input: &mut InputMap) -> ActionValues {
+        let mut live = input.sample();
+        match self {
+            ReplayMode::Live => live,
+            ReplayMode::Record(recorder) => {
+                if let Err(err) = recorder.record(&live) {
+                    println!("recording stopped: {err}");
+                    *self = ReplayMode::Live;
+                }
+                live
+            },
+            ReplayMode::Playback(playback) => match playback.next_tick() {
+                Some(mut values) => {
+                    values.remove(&Action::ToggleDebug);
+                    if let Some(value) = live.remove(&Action::ToggleDebug) {
+                        values.insert(Action::ToggleDebug, value);
+                    }
+                    values
+                },
+                None => {
+                    println!("playback finished");
+                    *self = ReplayMode::Live;
+                    live
+                },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use crate::{input::{Action, ActionValues}, player::config::PlayerConfig, save::Abilities};
+
+    use super::{Playback, Recorder, ReplayHeader};
+
+    #[test]
+    fn test_record_playback() {
+        let path = env::temp_dir().join("replay_test.jsonl");
+        let path = path.to_str().unwrap();
+        let abilities = Abilities { dash: true, ..Default::default() };
+        let config = PlayerConfig { walk_speed: 0.35, ..Default::default() };
+        let header = ReplayHeader { level: "src/res/map.json".into(), seed: 42, abilities, config };
+        let ticks: Vec<ActionValues> = vec![
+            ActionValues::new(),
+            ActionValues::from([(Action::MoveRight, 1.0)]),
+            ActionValues::from([(Action::MoveRight, 1.0), (Action::Jump, 1.0)]),
+        ];
+
+        let mut recorder = Recorder::new(path, &header).unwrap();
+        for values in &ticks {
+            recorder.record(values).unwrap();
+        }
+        drop(recorder);
+
+        let mut playback = Playback::load(path).unwrap();
+        assert_eq!(playback.header, header);
+        for values in ticks {
+            assert_eq!(playback.next_tick(), Some(values));
+        }
+        assert_eq!(playback.next_tick(), None);
+    }
+}
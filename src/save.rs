@@ -0,0 +1,93 @@
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+//where the game keeps its progress, next to wherever it is run from
+pub const SAVE_PATH: &str = "save.json";
+
+//movement the player has to unlock before it can be used, so levels can gate areas behind them
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct Abilities {
+    pub wall_slide: bool,
+    pub wall_jump: bool,
+    pub dash: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Ability {
+    WallSlide,
+    WallJump,
+    Dash,
+}
+
+impl Abilities {
+    pub fn has(&self, ability: Ability) -> bool {
+        match ability {
+            Ability::WallSlide => self.wall_slide,
+            Ability::WallJump => self.wall_jump,
+            Ability::Dash => self.dash,
+        }
+    }
+
+    //returns false if it was already unlocked
+    pub fn unlock(&mut self, ability: Ability) -> bool {
+        let unlocked = match ability {
+            Ability::WallSlide => &mut self.wall_slide,
+            Ability::WallJump => &mut self.wall_jump,
+            Ability::Dash => &mut self.dash,
+        };
+        !std::mem::replace(unlocked, true)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SaveData {
+    pub abilities: Abilities,
+}
+
+impl SaveData {
+    //a missing or broken save starts a new game
+    pub fn load(path: &str) -> Self {
+        fs::read_to_string(path).ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use super::{Ability, SaveData};
+
+    #[test]
+    fn test_old_saves_load() {
+        //saves written before an ability existed still load, with it locked
+        let save: SaveData = serde_json::from_str(r#"{"abilities": {"dash": true}}"#).unwrap();
+        assert!(save.abilities.dash);
+        assert!(!save.abilities.wall_jump);
+        let save: SaveData = serde_json::from_str("{}").unwrap();
+        assert!(!save.abilities.dash);
+    }
+
+    #[test]
+    fn test_unlock_persists() {
+        let path = env::temp_dir().join("save_test.json");
+        let path = path.to_str().unwrap();
+        let mut save = SaveData::default();
+        assert!(save.abilities.unlock(Ability::WallJump));
+        assert!(!save.abilities.unlock(Ability::WallJump));
+        save.save(path).unwrap();
+
+        let loaded = SaveData::load(path);
+        assert_eq!(loaded.abilities, save.abilities);
+        assert!(loaded.abilities.wall_jump && !loaded.abilities.dash);
+    }
+}
\ No newline at end of file
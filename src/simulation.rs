@@ -0,0 +1,212 @@
+use cgmath::Vector2;
+
+use crate::{input::{ActionState, ActionValues}, physics::{boxCollider::ColliderId, trigger::TriggerEventKind, world::PhysicsWorld}, player::{state::PlayerState, Player}, save::Ability, TilesetManager::TilesetManager};
+
+//physics runs at a fixed rate no matter the frame rate, in milliseconds like the frame delta
+pub const TICK_MS: f32 = 1000.0 / 60.0;
+
+//everything that plays out in a level, without anything to do with drawing it
+//this can be stepped without a window, which is how gameplay gets tested
+pub struct Simulation {
+    pub terrain: TilesetManager,
+    pub world: PhysicsWorld,
+    pub player: Player,
+    //the actions for the tick being simulated
+    pub actions: ActionState,
+    //triggers for the map's checkpoints
+    checkpoints: Vec<ColliderId>,
+    //where the player comes back after dying, the last checkpoint touched or where it started
+    pub respawn_point: Vector2<f32>,
+    //triggers for the map's ability pickups
+    pickups: Vec<(ColliderId, Ability)>,
+    //abilities picked up during the last tick, so the game knows when to save
+    pub unlocked: Vec<Ability>,
+}
+
+impl Simulation {
+    pub fn new(level: &str, map_width: u32, player_pos: Vector2<f32>) -> Self {
+        let terrain = TilesetManager::new(level, map_width);
+        let mut world = PhysicsWorld::new(50.0);
+        let checkpoints = terrain.checkpoints.iter().map(|checkpoint| world.add_trigger(checkpoint.clone())).collect();
+        let pickups = terrain.pickups.iter().map(|(pickup, ability)| (world.add_trigger(pickup.clone()), *ability)).collect();
+        Self {
+            terrain,
+            world,
+            player: Player::new(player_pos),
+            actions: ActionState::default(),
+            checkpoints,
+            respawn_point: player_pos,
+            pickups,
+            unlocked: vec![],
+        }
+    }
+
+    //runs one tick with the given actions
+    pub fn step(&mut self, values: ActionValues) {
+        self.actions.advance(values);
+        self.world.step(TICK_MS, &self.terrain);
+        self.player.handle_input(&self.actions, TICK_MS, &self.terrain, &mut self.world);
+        self.world.update_triggers(self.player.collider());
+        self.update_checkpoints();
+        self.update_pickups();
+
+        if self.player.pos.y < self.terrain.kill_plane() {
+            self.player.kill();
+        }
+        if self.player.state() == PlayerState::Dead && self.player.state_time() >= self.player.config.respawn_delay {
+            self.player.respawn(self.respawn_point);
+        }
+    }
+
+    //touching a checkpoint moves the respawn point to stand on the bottom of it
+    fn update_checkpoints(&mut self) {
+        for event in self.world.trigger_events() {
+            if event.kind != TriggerEventKind::Enter || event.other != ColliderId::Player || !self.checkpoints.contains(&event.trigger) {
+                continue;
+            }
+            if let Some(checkpoint) = self.world.collider(event.trigger) {
+                self.respawn_point = Vector2::new(checkpoint.pos.x, checkpoint.min().y + self.player.collider().size().y / 2.0);
+            }
+        }
+    }
+
+    //touching a pickup unlocks its ability for good, pickups for abilities the player already has do nothing
+    fn update_pickups(&mut self) {
+        self.unlocked.clear();
+        for event in self.world.trigger_events() {
+            if event.kind != TriggerEventKind::Enter || event.other != ColliderId::Player {
+                continue;
+            }
+            for (_, ability) in self.pickups.iter().filter(|(trigger, _)| *trigger == event.trigger) {
+                if self.player.abilities.unlock(*ability) {
+                    self.unlocked.push(*ability);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+
+    use cgmath::Vector2;
+
+    use crate::{input::{Action, ActionValues}, player::state::PlayerState, save::Ability};
+
+    use super::Simulation;
+
+    #[test]
+    fn test_player_lands_on_platform() {
+        let mut sim = Simulation::new("src/res/map.json", 800, Vector2::new(0.0, 0.0));
+        let platform = sim.terrain.tile_box(5, 2);
+        sim.player.pos = Vector2::new(platform.pos.x, platform.max().y + 100.0);
+
+        for _ in 0..120 {
+            sim.step(ActionValues::new());
+        }
+
+        let feet = sim.player.collider().min().y;
+        assert!((feet - platform.max().y).abs() < 0.1, "player's feet at {feet}, platform top at {}", platform.max().y);
+        assert!((sim.player.pos.x - platform.pos.x).abs() < 0.001);
+        assert_eq!(sim.player.state(), PlayerState::Idle);
+    }
+
+    #[test]
+    fn test_removed_tiles_stop_colliding() {
+        let mut sim = Simulation::new("src/res/map.json", 800, Vector2::new(0.0, 0.0));
+        let platform = sim.terrain.tile_box(5, 2);
+        sim.player.pos = Vector2::new(platform.pos.x, platform.max().y + 10.0);
+        for _ in 0..30 {
+            sim.step(ActionValues::new());
+        }
+        assert_eq!(sim.player.state(), PlayerState::Idle);
+
+        //dig a hole through the platform, wider than the player, and it falls straight through
+        for x in 4..=6 {
+            for y in 2..=4 {
+                sim.terrain.set_solid(x, y, false);
+            }
+        }
+        for _ in 0..60 {
+            sim.step(ActionValues::new());
+        }
+        assert!(sim.player.collider().max().y < sim.terrain.tile_box(5, 4).min().y, "player stopped at {}", sim.player.pos.y);
+    }
+
+    #[test]
+    fn test_falling_off_the_map_respawns() {
+        let mut sim = Simulation::new("src/res/map.json", 800, Vector2::new(0.0, 0.0));
+        sim.respawn_point = Vector2::new(sim.terrain.tile_box(5, 2).pos.x, 300.0);
+        //well outside the map, where there is nothing to land on
+        sim.player.pos = Vector2::new(2000.0, 0.0);
+        sim.player.health.current = 1;
+
+        let mut died = false;
+        for _ in 0..600 {
+            sim.step(ActionValues::new());
+            died |= sim.player.state() == PlayerState::Dead;
+            if died && sim.player.state() != PlayerState::Dead {
+                break;
+            }
+        }
+
+        assert!(died);
+        assert_ne!(sim.player.state(), PlayerState::Dead);
+        assert!((sim.player.pos.x - sim.respawn_point.x).abs() < 0.001);
+        assert_eq!(sim.player.health.current, sim.player.health.max);
+    }
+
+    #[test]
+    fn test_pickup_unlocks_ability() {
+        let mut sim = Simulation::new("src/res/map.json", 800, Vector2::new(0.0, 0.0));
+        let pickup = sim.terrain.pickups[0].0.clone();
+        sim.player.pos = Vector2::new(pickup.pos.x, pickup.max().y + 50.0);
+        assert!(!sim.player.abilities.dash);
+
+        let mut unlocked = vec![];
+        for _ in 0..60 {
+            sim.step(ActionValues::new());
+            unlocked.extend(sim.unlocked.iter().copied());
+        }
+
+        assert!(sim.player.abilities.dash);
+        assert_eq!(unlocked, vec![Ability::Dash]);
+    }
+
+    #[test]
+    fn test_walk_over_slopes() {
+        //a floor along row 8 with a 45 degree hill on it and then a 2:1 hill, both one tile high
+        let mut tiles: Vec<String> = (0..24).map(|x| format!(r#"{{"id":"0","x":{x},"y":8}}"#)).collect();
+        for (x, id) in [(4, 1), (5, 0), (6, 0), (7, 0), (8, 2), (12, 3), (13, 4), (14, 0), (15, 0), (16, 5), (17, 6)] {
+            tiles.push(format!(r#"{{"id":"{id}","x":{x},"y":7}}"#));
+        }
+        let shapes = ["Slope45Right", "Slope45Left", "SlopeGentleRightLow", "SlopeGentleRightHigh", "SlopeGentleLeftHigh", "SlopeGentleLeftLow"];
+        let collision: Vec<String> = shapes.iter().enumerate().map(|(i, shape)| format!(r#""{}":{{"type":"Shape","shape":"{shape}"}}"#, i + 1)).collect();
+        let path = env::temp_dir().join("slopes_test.json");
+        let path = path.to_str().unwrap();
+        fs::write(path, format!(r#"{{"tileSize":16,"mapWidth":24,"mapHeight":10,"tileCollision":{{{}}},"layers":[{{"name":"ground","collider":true,"tiles":[{}]}}]}}"#, collision.join(","), tiles.join(","))).unwrap();
+
+        let mut sim = Simulation::new(path, 800, Vector2::new(0.0, 0.0));
+        sim.player.pos = sim.terrain.tile_box(1, 7).pos;
+        for _ in 0..30 {
+            sim.step(ActionValues::new());
+        }
+        assert_eq!(sim.player.state(), PlayerState::Idle);
+
+        //tile x goes towards -x in the world, so walking left crosses both hills up and down and walking right comes back over them
+        let hilltop = sim.terrain.tile_box(5, 7).max().y;
+        for (action, direction, end) in [(Action::MoveLeft, -1.0, sim.terrain.tile_box(20, 7).pos.x), (Action::MoveRight, 1.0, sim.terrain.tile_box(1, 7).pos.x)] {
+            let mut highest = f32::MIN;
+            let mut ticks = 0;
+            while (end - sim.player.pos.x) * direction > 0.0 {
+                sim.step(ActionValues::from([(action, 1.0)]));
+                assert_ne!(sim.player.state(), PlayerState::Fall, "lost the ground at x {} y {}", sim.player.pos.x, sim.player.pos.y);
+                highest = highest.max(sim.player.collider().min().y);
+                ticks += 1;
+                assert!(ticks < 600, "never got to the end of the map");
+            }
+            assert!((highest - hilltop).abs() < 0.5, "got up to {highest}, the hills are at {hilltop}");
+        }
+    }
+}
@@ -1,8 +1,11 @@
+use std::fs;
+
+use anyhow::Context;
 use bespoke_engine::{binding::{Descriptor, UniformBinding}, camera::{self, Camera}, model::Render, shader::{Shader, ShaderConfig}, texture::Texture};
-use cgmath::{Quaternion, Rotation, Vector3};
+use cgmath::{Quaternion, Rotation, Vector2, Vector3};
 use wgpu::{core::device, Device, Queue, RenderPass, TextureFormat};
 
-use crate::{billboard::Billboard, instance::Instance, load_resource, shaders::ShaderManager, window::Vertex};
+use crate::{aseprite::{Aseprite, Slice, SliceKey, PIVOT_SLICE}, physics::boxCollider::BoxCollider, animation::{AnimationEvent, Animator, Clip, SpriteSheet}, billboard::Billboard, instance::Instance, load_resource, shaders::ShaderManager, window::Vertex};
 
 
 
@@ -10,21 +13,105 @@ pub struct Sprite {
     sprite_image: UniformBinding<Texture>,
     billboard: Billboard,
     shader: String,
+    //frames of the texture when it is a sprite sheet, without one the whole texture is shown
+    sheet: Option<SpriteSheet>,
+    pub animator: Animator,
+    //sheet frame the billboard is showing
+    shown_frame: Option<usize>,
+    //where the sprite is held from, the billboard is moved so the pivot slice lands here
+    position: Vector3<f32>,
+    flip: [bool; 2],
+    //hitboxes and pivot points from aseprite, in pixels of a frame
+    slices: Vec<Slice>,
+    frame_size: [u32; 2],
+    //world units per pixel of a frame
+    pixel_size: f32,
 }
 
 
 impl Sprite {
     pub fn new(path: &str, device: &Device, queue: &Queue, camera_binding: & UniformBinding<[[f32; 4]; 4]>, format: TextureFormat, scale: f32, position: Vector3<f32>, shader: String) -> Self {
-        let sprite_image = UniformBinding::new(device, "sprite", Texture::from_bytes(device, queue, &load_resource(path).unwrap(), "image", Some(wgpu::FilterMode::Nearest)).unwrap(), None);
+        Self::with_sheet(path, device, queue, camera_binding, format, scale, position, shader, None)
+    }
+
+    //a sprite that shows one frame of the sheet at a time, scale is the width of a frame
+    pub fn with_sheet(path: &str, device: &Device, queue: &Queue, camera_binding: & UniformBinding<[[f32; 4]; 4]>, format: TextureFormat, scale: f32, position: Vector3<f32>, shader: String, sheet: Option<SpriteSheet>) -> Self {
+        Self::from_bytes(&load_resource(path).unwrap(), device, queue, camera_binding, format, scale, position, shader, sheet)
+    }
+
+    //a sprite with every frame and tag of an aseprite file, the tags are added as clips
+    pub fn from_aseprite(aseprite: &Aseprite, device: &Device, queue: &Queue, camera_binding: & UniformBinding<[[f32; 4]; 4]>, format: TextureFormat, scale: f32, position: Vector3<f32>, shader: String) -> Self {
+        let mut sprite = Self::from_bytes(&aseprite.png().unwrap(), device, queue, camera_binding, format, scale, position, shader, Some(aseprite.sheet.clone()));
+        for (name, clip) in &aseprite.clips {
+            sprite.add_clip(name, clip.clone());
+        }
+        sprite.slices = aseprite.slices.clone();
+        sprite.frame_size = aseprite.frame_size;
+        sprite.pixel_size = scale / aseprite.frame_size[0] as f32;
+        sprite.place(device);
+        sprite
+    }
+
+    //reads an aseprite file from disk, so artists can save over it without a rebuild
+    pub fn load_aseprite(path: &str, device: &Device, queue: &Queue, camera_binding: & UniformBinding<[[f32; 4]; 4]>, format: TextureFormat, scale: f32, position: Vector3<f32>, shader: String) -> anyhow::Result<Self> {
+        let aseprite = Aseprite::parse(&fs::read(path)?).with_context(|| format!("couldn't read {path}"))?;
+        Ok(Self::from_aseprite(&aseprite, device, queue, camera_binding, format, scale, position, shader))
+    }
+
+    fn from_bytes(bytes: &[u8], device: &Device, queue: &Queue, camera_binding: & UniformBinding<[[f32; 4]; 4]>, format: TextureFormat, scale: f32, position: Vector3<f32>, shader: String, sheet: Option<SpriteSheet>) -> Self {
+        let sprite_image = UniformBinding::new(device, "sprite", Texture::from_bytes(device, queue, bytes, "image", Some(wgpu::FilterMode::Nearest)).unwrap(), None);
         let mut sprite_dim = sprite_image.value.normalized_dimensions();
         sprite_dim = (1.0, sprite_dim.1/sprite_dim.0);
+        let first_frame = sheet.as_ref().and_then(|sheet| sheet.frames.first().copied());
+        if let Some(frame) = first_frame {
+            sprite_dim.1 *= frame[3] / frame[2];
+        }
         let rotation = Quaternion::look_at(Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
-        let billboard = Billboard::new(sprite_dim.0 * scale, sprite_dim.1 * scale, 1.0, position, rotation, device);
+        let mut billboard = Billboard::new(sprite_dim.0 * scale, sprite_dim.1 * scale, 1.0, position, rotation, device);
+        if let Some(frame) = first_frame {
+            billboard.set_uv_rect(frame, device);
+        }
         Self {
             sprite_image,
             shader,
             billboard,
+            sheet,
+            animator: Animator::default(),
+            shown_frame: first_frame.map(|_| 0),
+            position,
+            flip: [false; 2],
+            slices: vec![],
+            frame_size: [0; 2],
+            pixel_size: 0.0,
+        }
+    }
+
+    pub fn add_clip(&mut self, name: &str, clip: Clip) {
+        self.animator.add_clip(name, clip);
+    }
+
+    pub fn play(&mut self, name: &str) {
+        self.animator.play(name);
+    }
+
+    pub fn queue(&mut self, name: &str) {
+        self.animator.queue(name);
+    }
+
+    //moves the animation on by delta milliseconds and returns any clips that finished
+    pub fn update_animation(&mut self, delta: f32, device: &Device) -> Vec<AnimationEvent> {
+        self.animator.update(delta);
+        if let Some(frame) = self.animator.frame().filter(|frame| Some(*frame) != self.shown_frame) {
+            if let Some(uv_rect) = self.sheet.as_ref().and_then(|sheet| sheet.frames.get(frame)) {
+                self.billboard.set_uv_rect(*uv_rect, device);
+                self.shown_frame = Some(frame);
+                //the pivot can move from frame to frame
+                if !self.slices.is_empty() {
+                    self.place(device);
+                }
+            }
         }
+        self.animator.take_events()
     }
 
     pub fn render<'b, 's: 'b>(&'s mut self, render_pass: & mut RenderPass<'b>, shader_man: * mut ShaderManager) {
@@ -37,6 +124,56 @@ impl Sprite {
     }
 
     pub fn set_position(&mut self, new_pos: Vector3<f32>, device: &Device) {
-        self.billboard.set_position(new_pos, device);
+        self.position = new_pos;
+        self.place(device);
+    }
+
+    pub fn set_flip(&mut self, flip: [bool; 2], device: &Device) {
+        if flip != self.flip {
+            self.flip = flip;
+            self.billboard.set_flip(flip, device);
+            self.place(device);
+        }
+    }
+
+    //the named slice's key for the frame being shown
+    pub fn slice(&self, name: &str) -> Option<&SliceKey> {
+        self.slices.iter().find(|slice| slice.name == name)?.key(self.shown_frame?)
+    }
+
+    //the named slice on the frame being shown as a box in the world, for hitboxes
+    pub fn slice_box(&self, name: &str) -> Option<BoxCollider> {
+        let rect = self.slice(name)?.rect;
+        let corners = [self.to_world([rect[0] as f32, rect[1] as f32]), self.to_world([(rect[0] + rect[2]) as f32, (rect[1] + rect[3]) as f32])];
+        let min = Vector2::new(corners[0].x.min(corners[1].x), corners[0].y.min(corners[1].y));
+        let max = Vector2::new(corners[0].x.max(corners[1].x), corners[0].y.max(corners[1].y));
+        Some(BoxCollider::new((min + max) / 2.0, max - min))
+    }
+
+    //where a pixel of the frame being shown is in the world, in the same x, y the player uses
+    fn to_world(&self, pixel: [f32; 2]) -> Vector2<f32> {
+        let pivot = self.slice(PIVOT_SLICE).map(|key| key.pivot_point()).unwrap_or([self.frame_size[0] as f32 / 2.0, self.frame_size[1] as f32 / 2.0]);
+        let mut offset = Vector2::new(pixel[0] - pivot[0], pivot[1] - pixel[1]) * self.pixel_size;
+        if self.flip[0] {
+            offset.x = -offset.x;
+        }
+        if self.flip[1] {
+            offset.y = -offset.y;
+        }
+        Vector2::new(self.position.z, self.position.y) + offset
+    }
+
+    //moves the billboard so the pivot is at position, sprites without a pivot are centered on it
+    fn place(&mut self, device: &Device) {
+        let center = self.to_world([self.frame_size[0] as f32 / 2.0, self.frame_size[1] as f32 / 2.0]);
+        self.billboard.set_position(Vector3::new(self.position.x, center.y, center.x), device);
+    }
+
+    pub fn set_tint(&mut self, tint: [f32; 4], device: &Device) {
+        self.billboard.set_tint(tint, device);
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32, device: &Device) {
+        self.billboard.set_opacity(opacity, device);
     }
 }
\ No newline at end of file
@@ -2,11 +2,12 @@ use std::{io::Cursor, mem, ops::Range};
 
 use bespoke_engine::{binding::{Descriptor, UniformBinding}, model::Render, shader::Shader, texture::Texture, window::{BasicVertex, SurfaceContext}};
 use bytemuck::{cast_slice, NoUninit};
+use cgmath::Vector2;
 use image::{GenericImageView, ImageError};
 use tiled::{DefaultResourceCache, Loader, ResourceCache};
 use wgpu::{util::{BufferInitDescriptor, DeviceExt}, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, Buffer, BufferUsages, Color, Device, ShaderStages, TextureFormat};
 
-use crate::window::ScreenInfo;
+use crate::{physics::{colliderGen::{EdgeChain, SolidMap, TileRect}, query::{grid_raycast, GridHit}}, window::ScreenInfo};
 
 pub struct Tiles {
     pub width: usize,
@@ -14,6 +15,10 @@ pub struct Tiles {
     pub tiles: [Vec<Tile>; 2],
     pub lights: Vec<Light>,
     pub solid: Vec<u32>,
+    //the solid tiles merged into as few rectangles as possible and their outline, in tile coordinates
+    //rebuilt whenever a tile's solidity changes
+    pub collision_rects: Vec<TileRect>,
+    pub edge_chains: Vec<EdgeChain>,
     pub layer1_buffer: Buffer,
     pub layer2_buffer: Buffer,
     pub lights_buffer: Buffer,
@@ -24,7 +29,8 @@ pub struct Tiles {
 }
 
 impl Tiles {
-    fn set_solid_static(solid: &mut Vec<u32>, i: usize, is_solid: bool) {
+    //the bit for tile i of a packed solid bitset, i is x * height + y
+    pub fn set_solid_static(solid: &mut [u32], i: usize, is_solid: bool) {
         if Self::get_solid_static(solid, i) != is_solid {
             let num = solid[i / 32];
             let bit = i % 32;
@@ -34,7 +40,7 @@ impl Tiles {
         }
     }
 
-    fn get_solid_static(solid: &Vec<u32>, i: usize) -> bool {
+    pub fn get_solid_static(solid: &[u32], i: usize) -> bool {
         let num = solid[i / 32];
         let bit = i % 32;
         let shifter = 1_u32 << bit;
@@ -47,6 +53,7 @@ impl Tiles {
         if x < self.width && y < self.height {
             let i = x * self.height + y;
             Self::set_solid_static(&mut self.solid, i, solid);
+            self.rebuild_colliders();
         }
     }
 
@@ -61,6 +68,7 @@ impl Tiles {
     pub fn set_solid_i(&mut self, i: usize, solid: bool) {
         if i < self.width*self.height {
             Self::set_solid_static(&mut self.solid, i, solid);
+            self.rebuild_colliders();
         }
     }
 
@@ -71,6 +79,24 @@ impl Tiles {
         return false;
     }
 
+    fn rebuild_colliders(&mut self) {
+        let mut solid_map = SolidMap::new(self.width as u32, self.height as u32);
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.get_solid(x, y) {
+                    solid_map.set(x as u32, y as u32, true);
+                }
+            }
+        }
+        self.collision_rects = solid_map.merge_rects();
+        self.edge_chains = solid_map.edge_chains();
+    }
+
+    //casts a ray through the solid bitset, origin and the hit are in tile coordinates
+    pub fn raycast(&self, origin: Vector2<f32>, dir: Vector2<f32>, max_distance: f32) -> Option<GridHit> {
+        grid_raycast(origin, dir, max_distance, |x, y| x >= 0 && y >= 0 && self.get_solid(x as usize, y as usize))
+    }
+
     #[allow(unused)]
     pub fn test(width: usize, height: usize, device: &Device) -> Self {
         let mut layer1 = vec![];
@@ -149,7 +175,17 @@ impl Tiles {
             }
         }
         let lights = vec![Light {color: [1.0, 1.0, 1.0, 1.0], pos: [0.0, 0.0], paddings: [0.0, 0.0]}];
-        let solid = vec![0; (layer1.len() as f32 / 32.0).ceil() as usize];
+        let mut solid = vec![0; (layer1.len() as f32 / 32.0).ceil() as usize];
+        //any tile on a layer called "Collision" is solid
+        if let Some(collision_src) = map.layers().filter(|layer| layer.name == "Collision").last().and_then(|layer| layer.as_tile_layer()) {
+            for x in 0..map.width {
+                for y in 0..map.height {
+                    if collision_src.get_tile(x as i32, y as i32).is_some() {
+                        Self::set_solid_static(&mut solid, (x * map.height + y) as usize, true);
+                    }
+                }
+            }
+        }
         Self::new([layer1, layer2], lights, solid, map.width as usize, map.height as usize, device)
     }
 
@@ -240,7 +276,9 @@ impl Tiles {
                 }
             ]
         });
-        Self { width, height, tiles, lights, solid, layer1_buffer, layer2_buffer, lights_buffer, tiles_bind_group, tiles_bind_group_layout: layout, tiles_map_size_buffer, solid_buffer }
+        let mut tiles = Self { width, height, tiles, lights, solid, collision_rects: Vec::new(), edge_chains: Vec::new(), layer1_buffer, layer2_buffer, lights_buffer, tiles_bind_group, tiles_bind_group_layout: layout, tiles_map_size_buffer, solid_buffer };
+        tiles.rebuild_colliders();
+        tiles
     }
 
     pub fn recreate_tiles(&mut self, layers: Range<usize>, device: &Device) {
@@ -1,13 +1,35 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, time::{SystemTime, UNIX_EPOCH}};
 
-use bespoke_engine::{billboard::Billboard, binding::{create_layout, Descriptor, UniformBinding}, camera::Camera, instance::Instance, model::{Render, ToRaw}, shader::{self, Shader, ShaderConfig}, texture::Texture, window::{SurfaceContext, WindowConfig, WindowHandler}};
+use bespoke_engine::{billboard::Billboard, binding::{create_layout, Descriptor, UniformBinding}, camera::Camera, model::{Render, ToRaw}, shader::{self, Shader, ShaderConfig}, texture::Texture, window::{SurfaceContext, WindowConfig, WindowHandler}};
 use bytemuck::{bytes_of, NoUninit, Pod, Zeroable};
 use cgmath::{Quaternion, Rotation, Vector2, Vector3};
 use tiled::Tile;
 use wgpu::{Color, Device, Limits, PipelineLayout, Queue, RenderPass, TextureFormat};
-use winit::{dpi::{PhysicalPosition, PhysicalSize}, event::KeyEvent, keyboard::{KeyCode, PhysicalKey::Code}};
-
-use crate::{load_resource, player::Player, shaders::ShaderManager, sprite::{self, Sprite}, TilesetManager::TilesetManager};
+use winit::{dpi::{PhysicalPosition, PhysicalSize}, event::KeyEvent, keyboard::PhysicalKey::Code};
+
+use crate::{animation::{Clip, PlayMode, SpriteSheet}, instance::Instance, debugDraw::{DebugDraw, DebugVertex}, input::{Action, Bindings, InputMap, BINDINGS_PATH}, load_resource, physics::{body::RigidBody, boxCollider::{LAYER_BODY, LAYER_TERRAIN}, kinematic::{KinematicBody, PathMode}, query::{box_cast, segment_cast}}, player::config::{ConfigWatcher, PLAYER_CONFIG_PATH}, prop::Prop, replay::ReplayMode, save::{SaveData, SAVE_PATH}, shaders::ShaderManager, simulation::{Simulation, TICK_MS}, sprite::{self, Sprite}, TilesetManager::TilesetManager};
+
+//if rendering falls this far behind, drop the extra time instead of trying to catch up
+const MAX_TICKS_PER_FRAME: u32 = 8;
+//player.png is one row of frames
+const PLAYER_FRAMES: u32 = 13;
+//how long each blink lasts while the player is invulnerable, in milliseconds
+const BLINK_TIME: f32 = 100.0;
+
+//the clips of player.png, named after the player states that play them
+fn player_clips() -> Vec<(&'static str, Clip)> {
+    vec![
+        ("idle", Clip::new(vec![0, 1], 400.0, PlayMode::Loop)),
+        ("run", Clip::new(vec![2, 3, 4, 5], 100.0, PlayMode::Loop)),
+        ("jump", Clip::new(vec![6], 100.0, PlayMode::Once)),
+        ("fall", Clip::new(vec![7], 100.0, PlayMode::Once)),
+        ("land", Clip::new(vec![8], 80.0, PlayMode::Once)),
+        ("wall_slide", Clip::new(vec![9], 100.0, PlayMode::Once)),
+        ("dash", Clip::new(vec![10], 100.0, PlayMode::Once)),
+        ("hurt", Clip::new(vec![11, 0], 100.0, PlayMode::Loop)),
+        ("dead", Clip::new(vec![12], 100.0, PlayMode::Once)),
+    ]
+}
 
 pub struct Window {
     screen_size: [f32; 2],
@@ -15,13 +37,23 @@ pub struct Window {
     start_time: u128,
     camera: Camera,
     camera_binding: UniformBinding<[[f32; 4]; 4]>,
-    keys_down: Vec<KeyCode>,
+    input: InputMap,
+    //whether ticks are recorded to or played back from a file
+    replay: ReplayMode,
     sprite: Sprite,
     sprite2: Sprite,
     shaderMan: ShaderManager,
     tileset_sprite: Sprite,
-    player: Player,
-    tileset_man: TilesetManager,
+    sim: Simulation,
+    player_sprite: Sprite,
+    //time that hasn't been simulated yet
+    accumulator: f32,
+    props: Vec<Prop>,
+    //one per map pickup, hidden once its ability is unlocked
+    pickup_sprites: Vec<Sprite>,
+    //collider overlay, toggled with the ToggleDebug action
+    debug_draw: DebugDraw,
+    player_config: ConfigWatcher,
 }
 
 #[repr(C)]
@@ -68,6 +100,8 @@ impl Window {
         let mut shaderMan = ShaderManager::new();
         let billboard_shader = Shader::new(include_str!("billboard.wgsl"), device, format, vec![&camera_binding.layout, &create_layout::<Texture>(device)], &[Vertex::desc(), Instance::desc()], Some(ShaderConfig {background: Some(false), ..Default::default()}));
         shaderMan.shaders.insert("billboard".into(), billboard_shader);
+        let debug_shader = Shader::new(include_str!("debug.wgsl"), device, format, vec![&camera_binding.layout], &[DebugVertex::desc(), Instance::desc()], Some(ShaderConfig {background: Some(false), ..Default::default()}));
+        shaderMan.shaders.insert("debug".into(), debug_shader);
         
         let start_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
 
@@ -78,11 +112,31 @@ impl Window {
         let sprite2 = Sprite::new(r"res\BGBack.png", device, queue, &camera_binding, format, 1500.0, Vector3::new(300.0, 0.0, 0.0), "billboard".into());
 
         
-        let tileset_man = TilesetManager::new("src/res/map.json", 800);
+        //during playback the abilities and config come from the recording instead
+        let mut player_config = ConfigWatcher::new(PLAYER_CONFIG_PATH);
+        let config = player_config.poll().unwrap_or_default();
+        let (replay, start) = ReplayMode::from_args(env::args().skip(1), "src/res/map.json", SaveData::load(SAVE_PATH).abilities, config);
+        TilesetManager::bake_image(&start.level, "src/res/spritesheet.png", "src/res/output.png");
         let tileset_sprite = Sprite::new(r"res\output.png", device, queue, &camera_binding, format, 800.0, Vector3::new(0.0, 0.0, 0.0), "billboard".into());
 
-        let player_sprite = Sprite::new(r"res\player.png", device, queue, &camera_binding, format, 50.0, Vector3::new(0.0, 0.0, 0.0), "billboard".into());
-        let player = Player::new(Vector2::new(100.0, 0.0), player_sprite);
+        let mut sim = Simulation::new(&start.level, 800, Vector2::new(100.0, 0.0));
+        let mut player_sprite = Sprite::with_sheet(r"res\player.png", device, queue, &camera_binding, format, 50.0, Vector3::new(0.0, 0.0, 0.0), "billboard".into(), Some(SpriteSheet::grid(PLAYER_FRAMES, 1)));
+        for (name, clip) in player_clips() {
+            player_sprite.add_clip(name, clip);
+        }
+        sim.player.abilities = start.abilities;
+        sim.player.set_config(start.config);
+
+        let crate_body = sim.world.add_body(RigidBody::new(Vector2::new(200.0, 50.0), Vector2::new(40.0, 40.0), 2.0));
+        let crate_sprite = Sprite::new(r"res\crate.png", device, queue, &camera_binding, format, 40.0, Vector3::new(0.0, 0.0, 0.0), "billboard".into());
+        let platform = sim.world.add_kinematic(KinematicBody::new(vec![Vector2::new(-50.0, -100.0), Vector2::new(-250.0, -100.0)], Vector2::new(100.0, 20.0), PathMode::PingPong, 1.5));
+        let platform_sprite = Sprite::new(r"res\platform.png", device, queue, &camera_binding, format, 100.0, Vector3::new(0.0, 0.0, 0.0), "billboard".into());
+        let props = vec![Prop::new(crate_body, crate_sprite), Prop::new(platform, platform_sprite)];
+        let pickup_sprites = sim.terrain.pickups.iter().map(|(pickup, _)| {
+            let mut sprite = Sprite::load_aseprite("src/res/pickup.aseprite", device, queue, &camera_binding, format, pickup.size().x, Vector3::new(0.0, pickup.pos.y, pickup.pos.x), "billboard".into()).unwrap();
+            sprite.play("spin");
+            sprite
+        }).collect();
 
         Self {
             screen_size,
@@ -90,13 +144,19 @@ impl Window {
             start_time,
             camera,
             camera_binding,
-            keys_down: vec![],
+            input: InputMap::new(&Bindings::load(BINDINGS_PATH)),
+            replay,
             sprite,
             sprite2,
             shaderMan,
             tileset_sprite,
-            player,
-            tileset_man,
+            sim,
+            player_sprite,
+            accumulator: 0.0,
+            props,
+            pickup_sprites,
+            debug_draw: DebugDraw::new(device),
+            player_config,
         }
     }
 }
@@ -156,16 +216,74 @@ impl WindowHandler for Window {
     }
 
     fn render<'s: 'c, 'c>(&'s mut self, surface_ctx: &SurfaceContext, render_pass: & mut RenderPass<'c>, delta: f64) {
-        let speed = 0.2 * delta as f32;
+        //recordings only store the config the run started with, so hot reloading is left to live runs
+        if matches!(self.replay, ReplayMode::Live) {
+            if let Some(config) = self.player_config.poll() {
+                self.sim.player.set_config(config);
+            }
+        }
 
-        self.player.handle_input(&self.keys_down, &surface_ctx.device, delta as f32, &self.tileset_man.colliders);
-        
-        self.camera.eye.y = self.player.pos.y;
-        self.camera.eye.z = self.player.pos.x;
+        self.accumulator += delta as f32;
+        let mut ticks = 0;
+        let mut toggle_debug = false;
+        while self.accumulator >= TICK_MS && ticks < MAX_TICKS_PER_FRAME {
+            self.sim.step(self.replay.tick_input(&mut self.input));
+            //a playback shouldn't change the save of whoever is watching it
+            if !self.sim.unlocked.is_empty() && !matches!(self.replay, ReplayMode::Playback(_)) {
+                let mut save = SaveData::load(SAVE_PATH);
+                save.abilities = self.sim.player.abilities;
+                if let Err(err) = save.save(SAVE_PATH) {
+                    println!("couldn't save: {err}");
+                }
+            }
+            toggle_debug |= self.sim.actions.just_pressed(Action::ToggleDebug);
+            self.accumulator -= TICK_MS;
+            ticks += 1;
+        }
+        if ticks == MAX_TICKS_PER_FRAME {
+            self.accumulator = self.accumulator.min(TICK_MS);
+        }
+        if toggle_debug {
+            self.debug_draw.toggle();
+            self.sim.world.record_casts = self.debug_draw.enabled;
+        }
+
+        //draw everything part way between the last two ticks so movement stays smooth at any frame rate
+        let alpha = self.accumulator / TICK_MS;
+        let player_pos = self.sim.player.render_pos(alpha);
+        self.player_sprite.set_position(Vector3::new(0.0, player_pos.y, player_pos.x), &surface_ctx.device);
+        self.player_sprite.set_flip([self.sim.player.facing < 0.0, false], &surface_ctx.device);
+        //animations follow simulated time, so they keep in step with a playback
+        self.player_sprite.play(self.sim.player.state().clip());
+        self.player_sprite.update_animation(ticks as f32 * TICK_MS, &surface_ctx.device);
+        //blink while invulnerable after a hit
+        //counts down with the simulation, so it pauses with the game and replays the same way
+        let blink = !((self.sim.player.health.invulnerable_time() / BLINK_TIME) as u32).is_multiple_of(2);
+        self.player_sprite.set_opacity(if blink { 0.3 } else { 1.0 }, &surface_ctx.device);
+        for prop in &mut self.props {
+            prop.update_sprite(&self.sim.world, alpha, &surface_ctx.device);
+        }
+        for sprite in &mut self.pickup_sprites {
+            sprite.update_animation(ticks as f32 * TICK_MS, &surface_ctx.device);
+        }
+
+        self.camera.eye.y = player_pos.y;
+        self.camera.eye.z = player_pos.x;
 
         
         self.camera_binding.set_data(&surface_ctx.device, self.camera.build_view_projection_matrix_raw());
 
+        if self.debug_draw.enabled {
+            //only what is on screen, which is the part of the sprite plane the camera's fov covers
+            let half_height = self.camera.eye.x.abs() * (self.camera.fovy.to_radians() / 2.0).tan();
+            let half_view = Vector2::new(half_height * self.camera.aspect, half_height);
+            //probes for the ground under the player and where its box would land, they get drawn along with every other cast the world recorded
+            let below = Vector2::new(0.0, -200.0);
+            segment_cast(&self.sim.terrain, &self.sim.world, player_pos, player_pos + below, Some(LAYER_TERRAIN | LAYER_BODY));
+            box_cast(&self.sim.terrain, &self.sim.world, self.sim.player.collider(), below, Some(LAYER_TERRAIN | LAYER_BODY));
+            self.debug_draw.physics(&self.sim.terrain, &self.sim.world, &self.sim.player, player_pos - half_view, player_pos + half_view);
+            self.debug_draw.upload(&surface_ctx.queue);
+        }
 
         let time = (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis()-self.start_time) as f32 / 1000.0;
         self.screen_info_binding.set_data(&surface_ctx.device, [self.screen_size[0], self.screen_size[1], time, 0.0]);
@@ -186,8 +304,19 @@ impl WindowHandler for Window {
 
         self.tileset_sprite.render(render_pass, man_ref1);
 
-        self.player.render(render_pass, man_ref1);
+        for prop in &mut self.props {
+            prop.render(render_pass, man_ref1);
+        }
+
+        for (sprite, (_, ability)) in self.pickup_sprites.iter_mut().zip(&self.sim.terrain.pickups) {
+            if !self.sim.player.abilities.has(*ability) {
+                sprite.render(render_pass, man_ref1);
+            }
+        }
+
+        self.player_sprite.render(render_pass, man_ref1);
 
+        self.debug_draw.render(render_pass, man_ref1);
     }
 
     fn config(&self) -> Option<WindowConfig> {
@@ -214,15 +343,7 @@ impl WindowHandler for Window {
     
     fn input_event(&mut self, device: &Device, input_event: &KeyEvent) {
         if let Code(code) = input_event.physical_key {
-            if input_event.state.is_pressed() {
-                if !self.keys_down.contains(&code) {
-                    self.keys_down.push(code);
-                }
-            } else {
-                if let Some(i) = self.keys_down.iter().position(|x| x == &code) {
-                    self.keys_down.remove(i);
-                }
-            }
+            self.input.key_event(code, input_event.state.is_pressed());
         }
     }
     